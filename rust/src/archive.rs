@@ -0,0 +1,127 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Archive types `--archive` can write, chosen by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Kind named by an archive path's extension (`.zip`, `.tar`, `.tar.gz` or `.tgz`)
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if lower.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pack `files` into an archive with the system `zip`/`tar`, keeping their paths
+/// relative to the folder they have in common
+///
+/// Audio is already compressed, so zip entries are stored rather than deflated.
+/// Without a `zip` command, bsdtar (macOS, Windows 10+) writes the zip instead; any
+/// other `tar` can't, so the archive fails.
+pub async fn create(archive_path: &str, files: &[String]) -> Result<(), String> {
+    let kind = ArchiveKind::from_path(archive_path).ok_or_else(|| format!("unknown archive type: {}", archive_path))?;
+    let files: Vec<PathBuf> = files.iter().map(|file| absolute(file)).collect();
+    let base = common_dir(&files);
+    let entries: Vec<String> = files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(&base)
+                .map(|relative| relative.to_string_lossy().to_string())
+                .unwrap_or_else(|_| file.to_string_lossy().to_string())
+        })
+        .collect();
+
+    // The tools run inside `base`, so the archive path must not be relative
+    let archive = absolute(archive_path);
+    if let Some(parent) = archive.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    // zip would add to an existing archive and tar can't, so both start fresh
+    let _ = std::fs::remove_file(&archive);
+
+    let result = match kind {
+        ArchiveKind::Zip => {
+            match run("zip", &["-q", "-0"], &archive, &entries, &base).await {
+                // GNU tar ignores `-a` for .zip and would write a tar archive under that name
+                Err(e) if e.kind() == ErrorKind::NotFound && is_bsdtar().await => {
+                    run("tar", &["-a", "-cf"], &archive, &entries, &base).await
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    return Err("no `zip` command found, install zip or use a .tar/.tar.gz archive".to_string());
+                }
+                result => result,
+            }
+        }
+        ArchiveKind::Tar => run("tar", &["-cf"], &archive, &entries, &base).await,
+        ArchiveKind::TarGz => run("tar", &["-czf"], &archive, &entries, &base).await,
+    };
+
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("the archiver reported an error".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Run an archiver as `<program> <flags> <archive> <entries...>` inside `base`
+async fn run(program: &str, flags: &[&str], archive: &Path, entries: &[String], base: &Path) -> std::io::Result<bool> {
+    let status = Command::new(program)
+        .args(flags)
+        .arg(archive)
+        .args(entries)
+        .current_dir(base)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()
+        .await?;
+    Ok(status.success())
+}
+
+/// Whether the system `tar` is bsdtar, the one that can write zip archives
+async fn is_bsdtar() -> bool {
+    match Command::new("tar").arg("--version").stdin(Stdio::null()).output().await {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).contains("bsdtar"),
+        Err(_) => false,
+    }
+}
+
+/// A path resolved against the current directory if it is relative
+fn absolute(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    match std::env::current_dir() {
+        Ok(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Deepest folder containing every file
+fn common_dir(files: &[PathBuf]) -> PathBuf {
+    let mut dirs = files.iter().map(|file| file.parent().map(Path::to_path_buf).unwrap_or_default());
+
+    let Some(mut common) = dirs.next() else {
+        return PathBuf::from(".");
+    };
+    for dir in dirs {
+        while !dir.starts_with(&common) {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    common
+}
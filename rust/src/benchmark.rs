@@ -0,0 +1,120 @@
+use crate::error::ConverterError;
+use crate::format::AudioFormat;
+use crate::options::DEFAULT_BITRATE;
+use crate::symbols::{self, Status};
+use crate::tempdir;
+use colored::*;
+use std::fs;
+use std::io::ErrorKind;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Length of the synthetic test tone, in seconds
+const TONE_SECONDS: u32 = 30;
+
+/// Outcome of encoding the test tone in one format
+struct BenchmarkResult {
+    format: AudioFormat,
+    elapsed: Duration,
+    size_kb: Option<f64>,
+    success: bool,
+}
+
+/// Encode a 440 Hz test tone in one format, timing the run
+async fn encode_tone(format: AudioFormat) -> Result<BenchmarkResult, ConverterError> {
+    let output_path = tempdir::path(&format!("benchmark.{}", format.extension()))?;
+    let output = output_path.to_string_lossy().to_string();
+
+    let mut args: Vec<String> = [
+        "-nostdin",
+        "-hide_banner",
+        "-loglevel", "error",
+        "-f", "lavfi",
+        "-i",
+    ]
+    .map(String::from)
+    .to_vec();
+    args.extend([
+        format!("sine=frequency=440:sample_rate=44100:duration={}", TONE_SECONDS),
+        "-acodec".to_string(),
+        format.default_encoder().to_string(),
+    ]);
+    if !format.is_lossless() {
+        args.extend(["-ab".to_string(), format!("{}k", DEFAULT_BITRATE)]);
+    }
+    args.extend(format.container_args().iter().map(|a| a.to_string()));
+    args.extend(["-y".to_string(), output.clone()]);
+
+    let started = Instant::now();
+    let status = Command::new("ffmpeg")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => ConverterError::FfmpegNotFound,
+            _ => ConverterError::Io(e),
+        })?;
+    let elapsed = started.elapsed();
+
+    let size_kb = fs::metadata(&output_path).ok().map(|m| m.len() as f64 / 1024.0);
+    let _ = fs::remove_file(&output_path);
+
+    Ok(BenchmarkResult {
+        format,
+        elapsed,
+        size_kb,
+        success: status.success(),
+    })
+}
+
+/// Encode a synthetic tone in every supported format and report time and size for each
+///
+/// Needs no input file, so it doubles as a check that every codec works on this ffmpeg build.
+pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", format!(
+        "⏱️  Benchmark: encoding a {}s test tone at {} kbps in each format",
+        TONE_SECONDS, DEFAULT_BITRATE
+    ).blue());
+    println!();
+
+    let mut failed = 0;
+    for format in AudioFormat::ALL {
+        let result = match encode_tone(format).await {
+            Ok(result) => result,
+            Err(ConverterError::FfmpegNotFound) => {
+                println!("{}", symbols::line(Status::Failure, ConverterError::FfmpegNotFound).red());
+                tempdir::exit(1);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let seconds = result.elapsed.as_secs_f64();
+        let speed = TONE_SECONDS as f64 / seconds.max(0.001);
+        match (result.success, result.size_kb) {
+            (true, Some(size_kb)) => println!("{}", format!(
+                "{}{:<14} {:>6.2}s  {:>7.1}x realtime  {:>8.1} KB",
+                symbols::prefix(Status::Success), result.format.to_string(), seconds, speed, size_kb
+            ).green()),
+            _ => {
+                println!("{}", format!(
+                    "{}{:<14} failed (encoder '{}')",
+                    symbols::prefix(Status::Failure), result.format.to_string(), result.format.default_encoder()
+                ).red());
+                failed += 1;
+            }
+        }
+    }
+
+    println!();
+    if failed > 0 {
+        println!("{}", format!("📊 {} of {} format(s) failed", failed, AudioFormat::ALL.len()).yellow());
+        tempdir::exit(1);
+    }
+    println!("{}", "📊 All formats encoded successfully".green());
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Stops in-flight conversions from the outside: Ctrl+C, the key listener or a front-end
+///
+/// Clones share one state, so cancelling any of them cancels them all. A token from
+/// `child_token` is cancelled along with its parent, but cancelling it leaves the parent
+/// alone, so one server job can be stopped without affecting the next. A cancelled
+/// conversion kills ffmpeg, removes its partial output and fails with `ConverterError::Cancelled`.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+    parent: Option<CancelToken>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A new token that is also cancelled when this one is
+    pub fn child_token(&self) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                parent: Some(self.clone()),
+                ..Inner::default()
+            }),
+        }
+    }
+
+    /// Cancel, waking everything waiting in `cancelled()`
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Wait until the token or one of its parents is cancelled (returns at once if it already is)
+    pub async fn cancelled(&self) {
+        match &self.inner.parent {
+            Some(parent) => {
+                let parent = Box::pin(parent.cancelled());
+                tokio::select! {
+                    _ = self.own_cancelled() => {}
+                    _ = parent => {}
+                }
+            }
+            None => self.own_cancelled().await,
+        }
+    }
+
+    /// Wait until this token itself is cancelled
+    async fn own_cancelled(&self) {
+        loop {
+            // Created before the check, so a cancel in between still wakes it
+            let notified = self.inner.notify.notified();
+            if self.inner.cancelled.load(Ordering::SeqCst) {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn is_cancelled(token: &CancelToken) -> bool {
+        tokio::time::timeout(Duration::from_millis(20), token.cancelled()).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_child_leaves_the_parent_alone() {
+        let parent = CancelToken::new();
+        let first = parent.child_token();
+        first.cancel();
+        assert!(is_cancelled(&first).await);
+        assert!(!is_cancelled(&parent).await);
+        assert!(!is_cancelled(&parent.child_token()).await);
+    }
+
+    #[tokio::test]
+    async fn cancelling_the_parent_cancels_its_children() {
+        let parent = CancelToken::new();
+        let child = parent.child_token();
+        parent.cancel();
+        assert!(is_cancelled(&child).await);
+        assert!(is_cancelled(&parent.child_token()).await);
+    }
+
+    #[tokio::test]
+    async fn waiting_child_wakes_when_the_parent_is_cancelled() {
+        let parent = CancelToken::new();
+        let child = parent.child_token();
+        let waiter = tokio::spawn(async move { child.cancelled().await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        parent.cancel();
+        tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,540 @@
+use crate::archive::ArchiveKind;
+use crate::format::{AudioFormat, Container};
+use crate::naming;
+use crate::options::Resampler;
+use crate::preset::Preset;
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+/// Command-line options for the video converter
+#[derive(Debug, Clone, Default, Parser)]
+#[command(name = "convert", version, about = "Interactive video to audio converter with file selection")]
+pub struct Args {
+    /// Read settings from this .env or TOML file instead of ../.env
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<String>,
+
+    /// Directory to scan for video files (skips the directory prompt)
+    #[arg(long)]
+    pub dir: Option<String>,
+
+    /// Convert every video file found instead of selecting one
+    #[arg(long)]
+    pub batch: bool,
+
+    /// Scan subdirectories as well
+    #[arg(short, long)]
+    pub recursive: bool,
+
+    /// Write outputs into this directory instead of next to the sources
+    #[arg(short, long)]
+    pub output_dir: Option<String>,
+
+    /// After a batch, pack the outputs into this .zip, .tar or .tar.gz and remove the loose files
+    #[arg(long, value_name = "FILE", requires = "batch", value_parser = parse_archive_path)]
+    pub archive: Option<String>,
+
+    /// Mirror the source folder structure under the output directory
+    #[arg(long, requires = "output_dir")]
+    pub mirror_structure: bool,
+
+    /// Only print the discovered video files and exit
+    #[arg(long)]
+    pub list: bool,
+
+    /// Print only one tab-separated line per conversion: input, output, status, size_mb, seconds
+    #[arg(long, requires = "batch", conflicts_with_all = ["list", "plan", "estimate"])]
+    pub oneline: bool,
+
+    /// In batch mode, skip files whose output exists and is newer than the source
+    #[arg(long, requires = "batch")]
+    pub incremental: bool,
+
+    /// Show which files a batch run would convert or skip as up to date, then exit
+    #[arg(long, conflicts_with_all = ["list", "probe", "serve", "estimate"])]
+    pub plan: bool,
+
+    /// Estimate how long converting the discovered files would take, then exit
+    #[arg(long, conflicts_with_all = ["list", "probe", "serve"])]
+    pub estimate: bool,
+
+    /// Recognize videos by their content (magic bytes) instead of their extension
+    #[arg(long)]
+    pub detect_content: bool,
+
+    /// Report how many files were scanned and which were skipped as unsupported, and each batch file's result as it finishes
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Encode a synthetic test tone in every format and report speed and size, then exit
+    #[arg(long)]
+    pub benchmark: bool,
+
+    /// Only use files whose name matches this glob, e.g. "lecture*" (repeatable)
+    #[arg(long = "include", value_name = "PATTERN")]
+    pub includes: Vec<String>,
+
+    /// Skip files whose name matches this glob, e.g. "*sample*" (repeatable, wins over --include)
+    #[arg(long = "exclude", value_name = "PATTERN")]
+    pub excludes: Vec<String>,
+
+    /// Print a summary of a video's audio (codec, bitrate, channels, ...) and exit
+    #[arg(long, value_name = "FILE")]
+    pub probe: Option<String>,
+
+    /// Convert every video dropped into DIR (or QUEUE_DIR from the config), moving each to done/ or failed/, until the queue is empty
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = "",
+        conflicts_with_all = ["dir", "batch", "recursive", "list", "plan", "estimate", "serve", "probe", "benchmark"]
+    )]
+    pub queue_dir: Option<String>,
+
+    /// Run as a server taking JSON conversion jobs, one per line, over this Unix socket
+    #[arg(long, value_name = "SOCKET", conflicts_with_all = ["batch", "list", "probe", "benchmark"])]
+    pub serve: Option<String>,
+
+    /// Print the --probe summary as JSON
+    #[arg(long, requires = "probe")]
+    pub json: bool,
+
+    /// Skip files smaller than this, e.g. "5MB" (KB, MB or GB; a bare number is MB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_mb)]
+    pub min_size: Option<f64>,
+
+    /// Skip files larger than this, e.g. "2GB" (KB, MB or GB; a bare number is MB)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size_mb)]
+    pub max_size: Option<f64>,
+
+    /// Ask before converting anything longer than this, e.g. "2h" or "1h30m" (a bare number is minutes); unattended runs fail instead
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub max_duration: Option<f64>,
+
+    /// Convert inputs over --max-duration or flagged by --check-integrity without asking
+    #[arg(long)]
+    pub force: bool,
+
+    /// Decode the end of each local input first and skip files that look truncated or corrupt (partial downloads)
+    #[arg(long)]
+    pub check_integrity: bool,
+
+    /// Order in which discovered files are listed [default: name, or recent with RECENT_FIRST]
+    #[arg(long, value_enum)]
+    pub sort: Option<SortOrder>,
+
+    /// Write the converted audio to this file (single-file mode; the format follows its extension)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["batch", "list", "output_dir"])]
+    pub output: Option<String>,
+
+    /// Output path pattern with {name}, {dir}, {ext}, {bitrate}, {format} and {index} placeholders
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "output")]
+    pub output_template: Option<String>,
+
+    /// Start from a named settings preset (individual options still override it)
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+
+    /// Output format (prompted for interactively when omitted)
+    #[arg(short, long, value_enum)]
+    pub format: Option<AudioFormat>,
+
+    /// Produce several formats from one decode, e.g. "mp3,flac" (the first is the main output)
+    #[arg(long, value_enum, value_delimiter = ',', conflicts_with = "format")]
+    pub formats: Vec<AudioFormat>,
+
+    /// Audio codec, to pair with --container (the same choices as --format)
+    #[arg(long, value_enum, conflicts_with_all = ["format", "formats"])]
+    pub codec: Option<AudioFormat>,
+
+    /// Container to put the audio in instead of the format's usual one, e.g. mka for AAC in Matroska
+    #[arg(long, value_enum, conflicts_with = "formats")]
+    pub container: Option<Container>,
+
+    /// Copy the source audio stream without re-encoding, into a container matching its codec (AAC → .m4a, AC3 → .ac3, ...)
+    #[arg(long, conflicts_with_all = [
+        "format", "formats", "codec", "preset", "compat", "ml", "bitrate", "channels", "downmix_mono",
+        "sample_rate", "normalize", "remove_silence", "gapless", "segments",
+    ])]
+    pub format_from_source: bool,
+
+    /// Copy the source audio instead of re-encoding when it's already in the requested codec at no higher bitrate (interactive runs ask)
+    #[arg(long, conflicts_with = "format_from_source")]
+    pub smart_copy: bool,
+
+    /// Format to use instead when ffmpeg lacks the encoder for --format
+    #[arg(long, value_enum)]
+    pub fallback_format: Option<AudioFormat>,
+
+    /// Audio bitrate in kbps (prompted for interactively when omitted)
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(8..=640))]
+    pub bitrate: Option<u32>,
+
+    /// Number of output channels (1 = mono, 2 = stereo)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=8))]
+    pub channels: Option<u32>,
+
+    /// Mix stereo down to mono by summing channels (instead of ffmpeg's default -ac 1)
+    #[arg(long)]
+    pub downmix_mono: bool,
+
+    /// Output sample rate in Hz
+    #[arg(long, value_parser = clap::value_parser!(u32).range(8000..=192000))]
+    pub sample_rate: Option<u32>,
+
+    /// Resampler used when the sample rate changes: swr (ffmpeg's own, fast) or soxr (SoX, higher quality)
+    #[arg(long, value_enum)]
+    pub resampler: Option<Resampler>,
+
+    /// soxr precision in bits, 15-33 (ffmpeg's default is 20; 28 is SoX's "very high" quality)
+    #[arg(long, value_name = "BITS", requires = "resampler", value_parser = clap::value_parser!(u32).range(15..=33))]
+    pub resampler_precision: Option<u32>,
+
+    /// Keep all channels of surround (5.1, 7.1) sources instead of downmixing to stereo (not for MP3)
+    #[arg(long)]
+    pub keep_surround: bool,
+
+    /// Normalize loudness (EBU R128, -16 LUFS)
+    #[arg(long)]
+    pub normalize: bool,
+
+    /// Write ReplayGain track gain/peak tags instead of changing the volume
+    #[arg(long, conflicts_with = "normalize")]
+    pub replaygain: bool,
+
+    /// Remove silent stretches from the audio
+    #[arg(long)]
+    pub remove_silence: bool,
+
+    /// Split each output into parts of this many minutes (name_001.mp3, name_002.mp3, ...)
+    #[arg(long, value_name = "MINUTES", value_parser = clap::value_parser!(u32).range(1..))]
+    pub segment_time: Option<u32>,
+
+    /// Hardware-accelerated decoding method (auto, videotoolbox, cuda, vaapi, ...)
+    #[arg(long, value_name = "METHOD")]
+    pub hwaccel: Option<String>,
+
+    /// Limit the threads ffmpeg uses per conversion (default: ffmpeg decides)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub ffmpeg_threads: Option<u32>,
+
+    /// Run ffmpeg at a lower CPU priority so other apps stay responsive (Unix nice level 1-19, default 10)
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        value_parser = clap::value_parser!(i32).range(1..=19)
+    )]
+    pub nice: Option<i32>,
+
+    /// Copy inputs that live on a network share (SMB, NFS, ...) to local disk before converting
+    #[arg(long)]
+    pub stage_locally: bool,
+
+    /// Slow but forgiving decode for damaged files: software decoding, errors ignored, explicit demuxer
+    #[arg(long, conflicts_with = "hwaccel")]
+    pub safe_mode: bool,
+
+    /// Title tag for the output
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Artist tag for the output
+    #[arg(long)]
+    pub artist: Option<String>,
+
+    /// Album tag for the output
+    #[arg(long)]
+    pub album: Option<String>,
+
+    /// Year tag for the output
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..=9999))]
+    pub year: Option<u16>,
+
+    /// Genre tag for the output
+    #[arg(long)]
+    pub genre: Option<String>,
+
+    /// Don't carry over any of the source's metadata (GPS, device, creation time, chapters); tags given here are still written
+    #[arg(long)]
+    pub strip_metadata: bool,
+
+    /// Write ffmpeg's full output to <output>.log, kept when the conversion fails (or always with =always)
+    #[arg(long, value_enum, value_name = "WHEN", num_args = 0..=1, default_missing_value = "failed")]
+    pub keep_logs: Option<KeepLogs>,
+
+    /// Make outputs byte-identical across runs: no source metadata, encoder version or timestamps
+    #[arg(long)]
+    pub reproducible: bool,
+
+    /// Pick which chapters to extract (single-file mode)
+    #[arg(long)]
+    pub chapters: bool,
+
+    /// Only convert a clip of this share of the length, centred on the middle (e.g. 10% → 45%–55%)
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        num_args = 0..=1,
+        default_missing_value = "10%",
+        value_parser = parse_percent,
+        conflicts_with = "chapters"
+    )]
+    pub sample: Option<f64>,
+
+    /// Encode a 30-second clip at two bitrates, e.g. 128,192, and compare their sizes and levels (and play them with PLAYER)
+    #[arg(
+        long,
+        value_name = "KBPS,KBPS",
+        value_parser = parse_bitrate_pair,
+        conflicts_with_all = ["batch", "bitrate", "format_from_source", "chapters", "sample", "preview", "segments", "record_duration"]
+    )]
+    pub compare: Option<[u32; 2]>,
+
+    /// Only convert the first SECONDS as a quick settings check, saved as <name>_preview
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["sample", "chapters"]
+    )]
+    pub preview: Option<u32>,
+
+    /// Record this much audio from a live stream, e.g. "30m" or "1h" (a bare number is minutes)
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        conflicts_with_all = ["preview", "sample", "chapters", "segments", "loop_count"]
+    )]
+    pub record_duration: Option<f64>,
+
+    /// Maximally compatible MP3 for old car stereos and the like (the same as --preset compat)
+    #[arg(long, conflicts_with_all = ["preset", "gapless"])]
+    pub compat: bool,
+
+    /// 16 kHz mono 16-bit PCM WAV for ML models, exactly (the same as --preset ml, but nothing can override it)
+    #[arg(long, conflicts_with_all = [
+        "preset", "compat", "format", "formats", "codec", "container", "format_from_source",
+        "channels", "downmix_mono", "sample_rate", "bitrate",
+    ])]
+    pub ml: bool,
+
+    /// Encode for gapless playback of split parts: sample-exact cuts, no MP3 bit reservoir, LAME delay/padding info
+    #[arg(long)]
+    pub gapless: bool,
+
+    /// Trim at the exact sample instead of the nearest keyframe; slower, as everything before the cut is decoded
+    #[arg(long)]
+    pub accurate_seek: bool,
+
+    /// Repeat the input audio N times in the output, e.g. to stretch a short ambient loop
+    #[arg(
+        long = "loop",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["chapters", "sample", "segments"]
+    )]
+    pub loop_count: Option<u32>,
+
+    /// Convert the segments listed in a timestamps file (`start end name` per line), one file each
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["chapters", "sample", "preview"])]
+    pub segments: Option<String>,
+
+    /// Character substituted for characters that aren't allowed in file names
+    #[arg(long, value_name = "CHAR", default_value_t = '_', value_parser = parse_replacement_char)]
+    pub replacement_char: char,
+
+    /// Command to run after each successful conversion ({input}, {output} and {size} are filled in)
+    #[arg(long, value_name = "COMMAND")]
+    pub on_success: Option<String>,
+
+    /// Command to run after each failed conversion ({input}, {output} and {size} are filled in)
+    #[arg(long, value_name = "COMMAND")]
+    pub on_failure: Option<String>,
+
+    /// Print a content-based audio fingerprint of each output (or of the --probe input)
+    #[arg(long)]
+    pub fingerprint: bool,
+
+    /// Post a desktop notification when the conversion or batch finishes
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Extra HTTP header for URL inputs, as "Name: value" (repeatable)
+    #[arg(long = "header", value_name = "HEADER", value_parser = parse_header)]
+    pub headers: Vec<String>,
+
+    /// AES-128 key (32 hex digits) for encrypted MP4/DASH inputs; HLS keys are fetched from the playlist
+    #[arg(long, value_name = "HEX", value_parser = parse_key)]
+    pub key: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+/// Subcommands that run instead of a conversion
+#[derive(Debug, Clone, Subcommand)]
+pub enum CliCommand {
+    /// Print a shell completion script (e.g. `convert completions bash > ~/.local/share/bash-completion/completions/convert`)
+    Completions {
+        /// Shell to write the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+impl Args {
+    /// Metadata tags given on the command line, as (key, value) pairs
+    pub fn tags(&self) -> Vec<(String, String)> {
+        [
+            ("title", self.title.clone()),
+            ("artist", self.artist.clone()),
+            ("album", self.album.clone()),
+            ("date", self.year.map(|y| y.to_string())),
+            ("genre", self.genre.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key.to_string(), v)))
+        .collect()
+    }
+}
+
+/// Make sure the filename replacement character is itself allowed in file names
+fn parse_replacement_char(raw: &str) -> Result<char, String> {
+    let mut chars = raw.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !naming::is_illegal_char(c) => Ok(c),
+        (Some(_), None) => Err(format!("'{}' is not allowed in file names", raw)),
+        _ => Err("expected a single character".to_string()),
+    }
+}
+
+/// Parse a percentage such as "10%" or "10" (0 < p <= 100)
+fn parse_percent(raw: &str) -> Result<f64, String> {
+    let percent: f64 = raw
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("expected a percentage like 10%, got \"{}\"", raw))?;
+    if percent > 0.0 && percent <= 100.0 {
+        Ok(percent)
+    } else {
+        Err("percentage must be above 0% and at most 100%".to_string())
+    }
+}
+
+/// Parse a size such as "500KB", "5MB" or "2GB" into megabytes
+fn parse_size_mb(raw: &str) -> Result<f64, String> {
+    let upper = raw.trim().to_uppercase();
+    let (number, factor) = if let Some(n) = upper.strip_suffix("GB") {
+        (n, 1024.0)
+    } else if let Some(n) = upper.strip_suffix("MB") {
+        (n, 1.0)
+    } else if let Some(n) = upper.strip_suffix("KB") {
+        (n, 1.0 / 1024.0)
+    } else {
+        (upper.as_str(), 1.0)
+    };
+
+    match number.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 => Ok(value * factor),
+        _ => Err(format!("expected a size like 5MB or 2GB, got \"{}\"", raw)),
+    }
+}
+
+/// Parse a duration such as "2h", "90m", "1h30m" or "45s" into seconds (a bare number is minutes)
+fn parse_duration(raw: &str) -> Result<f64, String> {
+    let invalid = || format!("expected a duration like 2h, 90m or 1h30m, got \"{}\"", raw);
+    let lower = raw.trim().to_lowercase();
+    if let Ok(minutes) = lower.parse::<f64>() {
+        return if minutes > 0.0 { Ok(minutes * 60.0) } else { Err(invalid()) };
+    }
+
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in lower.chars() {
+        let factor = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            _ => {
+                number.push(c);
+                continue;
+            }
+        };
+        let value: f64 = number.trim().parse().map_err(|_| invalid())?;
+        seconds += value * factor;
+        number.clear();
+    }
+
+    if !number.trim().is_empty() || seconds <= 0.0 {
+        return Err(invalid());
+    }
+    Ok(seconds)
+}
+
+/// Accept only archive paths whose extension names a supported archive type
+fn parse_archive_path(raw: &str) -> Result<String, String> {
+    match ArchiveKind::from_path(raw) {
+        Some(_) => Ok(raw.to_string()),
+        None => Err(format!("expected a .zip, .tar, .tar.gz or .tgz path, got \"{}\"", raw)),
+    }
+}
+
+/// Validate a "Name: value" HTTP header
+fn parse_header(raw: &str) -> Result<String, String> {
+    match raw.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() && !value.trim().is_empty() => {
+            Ok(format!("{}: {}", name.trim(), value.trim()))
+        }
+        _ => Err(format!("expected \"Name: value\", got \"{}\"", raw)),
+    }
+}
+
+/// Check a decryption key is 16 bytes of hex, with or without a leading "0x"
+fn parse_key(raw: &str) -> Result<String, String> {
+    let hex = raw.trim();
+    let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+    if hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(hex.to_lowercase())
+    } else {
+        Err("expected a 128-bit key as 32 hex digits, e.g. 00112233445566778899aabbccddeeff".to_string())
+    }
+}
+
+/// Parse `--compare`'s two bitrates, e.g. "128,192", each within `--bitrate`'s 8-640 kbps
+fn parse_bitrate_pair(raw: &str) -> Result<[u32; 2], String> {
+    let bitrates: Vec<u32> = raw
+        .split(',')
+        .map(|kbps| kbps.trim().trim_end_matches('k').parse().map_err(|_| format!("'{}' is not a bitrate in kbps", kbps.trim())))
+        .collect::<Result<_, _>>()?;
+    match bitrates[..] {
+        [a, b] if [a, b].iter().all(|kbps| (8..=640).contains(kbps)) => Ok([a, b]),
+        [_, _] => Err("bitrates must be between 8 and 640 kbps".to_string()),
+        _ => Err("expected two bitrates separated by a comma, e.g. 128,192".to_string()),
+    }
+}
+
+/// Which conversion logs `--keep-logs` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum KeepLogs {
+    /// Only those of failed conversions
+    Failed,
+    /// Every one
+    Always,
+}
+
+/// Ordering applied to scanned video files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// Alphabetical by file name (case-insensitive)
+    #[default]
+    Name,
+    /// Largest files first
+    Size,
+    /// Most recently modified first
+    Recent,
+}
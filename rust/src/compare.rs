@@ -0,0 +1,105 @@
+use crate::table::{self, Cell, Column};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Length of the clip encoded at each bitrate, in seconds
+pub const CLIP_SECONDS: f64 = 30.0;
+
+const COLUMNS: [Column; 5] = [
+    Column { heading: "Sample", right_aligned: false },
+    Column { heading: "Bitrate", right_aligned: true },
+    Column { heading: "Size", right_aligned: true },
+    Column { heading: "Mean volume", right_aligned: true },
+    Column { heading: "Peak", right_aligned: true },
+];
+
+/// Levels reported by ffmpeg's `volumedetect` filter, in dB
+#[derive(Debug, Clone, Copy)]
+pub struct Volume {
+    pub mean_db: f64,
+    pub max_db: f64,
+}
+
+/// One clip of the comparison, encoded at one bitrate
+pub struct Sample {
+    /// Letter the sample is offered under (A, B)
+    pub label: char,
+    pub bitrate_kbps: u32,
+    pub path: PathBuf,
+    pub volume: Option<Volume>,
+}
+
+impl Sample {
+    fn size_kb(&self) -> Option<f64> {
+        fs::metadata(&self.path).ok().map(|m| m.len() as f64 / 1024.0)
+    }
+}
+
+/// Start and end of the clip: `CLIP_SECONDS` from the middle, or the start if the length is unknown
+pub fn clip_bounds(duration: Option<f64>) -> (f64, f64) {
+    match duration {
+        Some(duration) if duration > CLIP_SECONDS => {
+            let start = (duration - CLIP_SECONDS) / 2.0;
+            (start, start + CLIP_SECONDS)
+        }
+        Some(duration) => (0.0, duration),
+        None => (0.0, CLIP_SECONDS),
+    }
+}
+
+/// Measure a file's mean and peak volume with a decode-only `volumedetect` pass
+pub async fn volumedetect(path: &Path) -> Option<Volume> {
+    let output = Command::new("ffmpeg")
+        .args(["-nostdin", "-hide_banner", "-i"])
+        .arg(path)
+        .args(["-af", "volumedetect", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+
+    // Reported on stderr as e.g. "[Parsed_volumedetect_0 @ 0x...] mean_volume: -21.3 dB"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let level = |name: &str| -> Option<f64> {
+        let line = stderr.lines().find(|line| line.contains(name))?;
+        line.split(name).nth(1)?.split_whitespace().next()?.parse().ok()
+    };
+    Some(Volume {
+        mean_db: level("mean_volume:")?,
+        max_db: level("max_volume:")?,
+    })
+}
+
+/// Print the samples side by side
+pub fn print(samples: &[Sample]) {
+    let db = |value: Option<f64>| Cell::plain(value.map_or("—".to_string(), |db| format!("{:.1} dB", db)));
+    let rows: Vec<Vec<Cell>> = samples
+        .iter()
+        .map(|sample| {
+            vec![
+                Cell::plain(sample.label.to_string()),
+                Cell::plain(format!("{} kbps", sample.bitrate_kbps)),
+                Cell::plain(sample.size_kb().map_or("—".to_string(), |kb| format!("{:.0} KB", kb))),
+                db(sample.volume.map(|volume| volume.mean_db)),
+                db(sample.volume.map(|volume| volume.max_db)),
+            ]
+        })
+        .collect();
+    table::print(&COLUMNS, &rows);
+}
+
+/// Play a file with the configured player command (split on spaces, the file appended)
+pub async fn play(player: &str, path: &Path) -> std::io::Result<bool> {
+    let mut words = player.split_whitespace();
+    let Some(program) = words.next() else {
+        return Ok(false);
+    };
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .await?;
+    Ok(status.success())
+}
@@ -1,41 +1,242 @@
+use crate::error::ConverterError;
+use crate::paths::expand_tilde;
+use crate::symbols::{Status, Symbols};
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::path::Path;
 
+/// Environment variable that overrides the default directory from any file
+const DEFAULT_DIR_ENV: &str = "VIDEO_TO_AUDIO_DEFAULT_DIR";
+
+/// Environment variable that overrides the queue directory from any file
+const QUEUE_DIR_ENV: &str = "VIDEO_TO_AUDIO_QUEUE_DIR";
+
 /// Configuration settings for the video converter
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub default_dir: String,
+    /// Directories offered as scan roots (the first one is the primary default)
+    pub default_dirs: Vec<String>,
+    /// List the most recently modified files first by default
+    pub recent_first: bool,
+    /// Post a desktop notification when a conversion or batch finishes
+    pub notify: bool,
+    /// Pre-selected answer when asked whether to use the default directory
+    pub default_use_dir: bool,
+    /// How many recently converted files to offer at the top of the file list (0 turns it off)
+    pub recent_files: usize,
+    /// A formats.toml layered over the bundled one (extra video extensions, output extensions)
+    pub formats_file: Option<String>,
+    /// Folder `--queue-dir` processes when given without one
+    pub queue_dir: Option<String>,
+    /// Symbols shown in front of success, failure, skip and convert messages
+    pub symbols: Symbols,
+    /// Command that plays `--compare` samples, e.g. `mpv` or `ffplay -nodisp -autoexit`
+    pub player: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
-            default_dir: "/Users/hackyourfuture/Downloads".to_string(),
+            default_dirs: vec!["/Users/hackyourfuture/Downloads".to_string()],
+            recent_first: false,
+            notify: false,
+            default_use_dir: true,
+            recent_files: 5,
+            formats_file: None,
+            queue_dir: None,
+            symbols: Symbols::default(),
+            player: None,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from environment file
-    pub fn load() -> Self {
+    /// The primary default directory
+    pub fn default_dir(&self) -> &str {
+        &self.default_dirs[0]
+    }
+
+    /// Load configuration, from `explicit_path` if given
+    ///
+    /// Precedence: `VIDEO_TO_AUDIO_DEFAULT_DIR` (and `VIDEO_TO_AUDIO_QUEUE_DIR`) > `--config` file (or `../.env`) > compiled default.
+    /// A missing or unreadable file at an explicit path is an error rather than a fallback,
+    /// and so is a symbol that can't be displayed.
+    pub fn load(explicit_path: Option<&str>) -> Result<Self, ConverterError> {
         let mut config = Config::default();
-        
-        // Try to load .env file from parent directory
-        let env_path = Path::new("../.env");
-        
-        if env_path.exists() {
-            if let Ok(env_vars) = load_env_file(env_path) {
-                if let Some(default_dir) = env_vars.get("DEFAULT_DIR") {
-                    config.default_dir = default_dir.trim().to_string();
+
+        let env_vars = match explicit_path {
+            Some(path) => {
+                let path = expand_tilde(path);
+                let vars = load_config_file(Path::new(&path)).map_err(|e| ConverterError::ConfigUnreadable {
+                    path: path.clone(),
+                    reason: e.to_string(),
+                })?;
+                Some((path, vars))
+            }
+            // Otherwise try the .env file in the parent directory
+            None => {
+                let env_path = Path::new("../.env");
+                if env_path.exists() {
+                    load_env_file(env_path).ok().map(|vars| (env_path.display().to_string(), vars))
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some((path, env_vars)) = env_vars {
+            // DEFAULT_DIRS holds several roots separated like PATH (":" or ";" on Windows)
+            if let Some(default_dirs) = env_vars.get("DEFAULT_DIRS") {
+                let dirs: Vec<String> = env::split_paths(default_dirs.trim())
+                    .map(|dir| expand_tilde(&dir.to_string_lossy()))
+                    .filter(|dir| !dir.is_empty())
+                    .collect();
+                if !dirs.is_empty() {
+                    config.default_dirs = dirs;
                 }
+            } else if let Some(default_dir) = env_vars.get("DEFAULT_DIR") {
+                config.default_dirs = vec![expand_tilde(default_dir.trim())];
+            }
+
+            if let Some(recent_first) = env_vars.get("RECENT_FIRST") {
+                config.recent_first = parse_bool(recent_first);
+            }
+
+            if let Some(notify) = env_vars.get("NOTIFY") {
+                config.notify = parse_bool(notify);
+            }
+
+            if let Some(default_use_dir) = env_vars.get("DEFAULT_USE_DIR") {
+                config.default_use_dir = parse_bool(default_use_dir);
+            }
+
+            if let Some(recent_files) = env_vars.get("RECENT_FILES").and_then(|n| n.trim().parse().ok()) {
+                config.recent_files = recent_files;
+            }
+
+            if let Some(formats_file) = env_vars.get("FORMATS_FILE") {
+                config.formats_file = Some(expand_tilde(formats_file.trim()));
+            }
+
+            if let Some(queue_dir) = env_vars.get("QUEUE_DIR").filter(|dir| !dir.trim().is_empty()) {
+                config.queue_dir = Some(expand_tilde(queue_dir.trim()));
+            }
+
+            if let Some(player) = env_vars.get("PLAYER").filter(|player| !player.trim().is_empty()) {
+                config.player = Some(player.trim().to_string());
+            }
+
+            // `SYMBOLS_SUCCESS=✔` in a .env file, `success = "✔"` under `[symbols]` in TOML
+            for status in Status::ALL {
+                if let Some(symbol) = env_vars.get(&format!("SYMBOLS_{}", status.key().to_uppercase())) {
+                    config.symbols.set(status, symbol).map_err(|reason| ConverterError::ConfigUnreadable {
+                        path: path.clone(),
+                        reason,
+                    })?;
+                }
+            }
+        }
+
+        // Lets containers and CI set the directory without writing a file
+        if let Ok(default_dir) = env::var(DEFAULT_DIR_ENV) {
+            if !default_dir.trim().is_empty() {
+                config.default_dirs = vec![expand_tilde(default_dir.trim())];
+            }
+        }
+        if let Ok(queue_dir) = env::var(QUEUE_DIR_ENV) {
+            if !queue_dir.trim().is_empty() {
+                config.queue_dir = Some(expand_tilde(queue_dir.trim()));
             }
         }
         
-        config
+        Ok(config)
+    }
+}
+
+/// Interpret common truthy spellings (`true`, `1`, `yes`, `on`)
+fn parse_bool(value: &str) -> bool {
+    matches!(value.trim().to_lowercase().as_str(), "true" | "1" | "yes" | "on")
+}
+
+/// Read a config file given with `--config`: TOML when it ends in `.toml`, `.env` syntax otherwise
+fn load_config_file(path: &Path) -> Result<HashMap<String, String>, std::io::Error> {
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml")) {
+        load_toml_file(path)
+    } else {
+        load_env_file(path)
+    }
+}
+
+/// Settings a TOML config file can hold, named like the `.env` keys in lowercase
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TomlConfig {
+    default_dir: Option<String>,
+    default_dirs: Option<Vec<String>>,
+    recent_first: Option<bool>,
+    notify: Option<bool>,
+    default_use_dir: Option<bool>,
+    recent_files: Option<usize>,
+    formats_file: Option<String>,
+    queue_dir: Option<String>,
+    player: Option<String>,
+    symbols: TomlSymbols,
+}
+
+/// The `[symbols]` table of a TOML config
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TomlSymbols {
+    success: Option<String>,
+    failure: Option<String>,
+    skip: Option<String>,
+    convert: Option<String>,
+}
+
+impl TomlConfig {
+    /// The same keys a .env file uses
+    ///
+    /// Keys are upper-cased (`default_dirs` → `DEFAULT_DIRS`) and prefixed with their table's
+    /// name (`success` under `[symbols]` → `SYMBOLS_SUCCESS`), and arrays are joined like a
+    /// PATH so `DEFAULT_DIRS` can hold several folders.
+    fn into_vars(self) -> HashMap<String, String> {
+        let default_dirs = self.default_dirs.map(|dirs| {
+            env::join_paths(dirs.iter().filter(|dir| !dir.is_empty()))
+                .map(|joined| joined.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+
+        [
+            ("DEFAULT_DIR", self.default_dir),
+            ("DEFAULT_DIRS", default_dirs),
+            ("RECENT_FIRST", self.recent_first.map(|b| b.to_string())),
+            ("NOTIFY", self.notify.map(|b| b.to_string())),
+            ("DEFAULT_USE_DIR", self.default_use_dir.map(|b| b.to_string())),
+            ("RECENT_FILES", self.recent_files.map(|n| n.to_string())),
+            ("FORMATS_FILE", self.formats_file),
+            ("QUEUE_DIR", self.queue_dir),
+            ("PLAYER", self.player),
+            ("SYMBOLS_SUCCESS", self.symbols.success),
+            ("SYMBOLS_FAILURE", self.symbols.failure),
+            ("SYMBOLS_SKIP", self.symbols.skip),
+            ("SYMBOLS_CONVERT", self.symbols.convert),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect()
     }
 }
 
+/// Read a TOML config file into the same keys a .env file uses
+fn load_toml_file(path: &Path) -> Result<HashMap<String, String>, std::io::Error> {
+    let content = fs::read_to_string(path)?;
+    let config: TomlConfig =
+        toml::from_str(&content).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string().trim_end()))?;
+    Ok(config.into_vars())
+}
+
 /// Parse .env file and return key-value pairs
 fn load_env_file(path: &Path) -> Result<HashMap<String, String>, std::io::Error> {
     let content = fs::read_to_string(path)?;
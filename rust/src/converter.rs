@@ -1,229 +1,3026 @@
+use crate::archive;
+use crate::benchmark;
+use crate::cancel::CancelToken;
+use crate::cli::{Args, KeepLogs, SortOrder};
+use crate::compare::{self, Sample};
 use crate::config::Config;
+use crate::data_uri;
+use crate::error::ConverterError;
+use crate::estimate;
+use crate::ffmpeg;
+use crate::fingerprint;
+use crate::format::{AudioFormat, StreamCopy};
+use crate::formats::Formats;
+use crate::history::RecentFiles;
+use crate::hooks;
+use crate::keys::{KeyCommand, KeyListener};
+use crate::lines::{self, BoundedLines};
+use crate::lock::OutputLock;
+use crate::loudness;
+use crate::naming::{self, fit_filename, sanitize_filename};
+use crate::notify;
+use crate::oneline::OnelineReport;
+use crate::options::{self, ConversionOptions, Resampler, SmartCopy, BITRATES, DEFAULT_BITRATE};
+use crate::paths::expand_tilde;
+use crate::pattern;
+use crate::preset::Preset;
+use crate::probe::{self, AudioStream, Chapter, ProbeCache};
+use crate::progress::{self, ProgressCallback, ProgressTracker, SpinnerGuard};
+use crate::recovery;
+use crate::remote::{self, StagedInput};
+use crate::segments;
+use crate::serve::{self, Event, Request};
+use crate::sniff;
+use crate::tempdir;
+use crate::symbols::{self, Status};
+use crate::table::{self, Cell, Color, Column};
+use crate::template;
 use crate::video_file::VideoFile;
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
-use inquire::{Confirm, Select, Text};
+use inquire::list_option::ListOption;
+use inquire::validator::Validation;
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::fs;
+use std::io::{ErrorKind, IsTerminal};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
+use tokio::sync::mpsc;
 use url::Url;
+use walkdir::WalkDir;
+
+/// How long a queued file must go unmodified before it's taken, in case it's still being copied in
+const QUEUE_SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Columns of the table printed after a batch
+const BATCH_COLUMNS: [Column; 6] = [
+    Column { heading: "File", right_aligned: false },
+    Column { heading: "Status", right_aligned: false },
+    Column { heading: "Input", right_aligned: true },
+    Column { heading: "Output", right_aligned: true },
+    Column { heading: "Duration", right_aligned: true },
+    Column { heading: "Time", right_aligned: true },
+];
+
+/// How much of the end of an input `--check-integrity` decodes
+const INTEGRITY_TAIL_SECONDS: u32 = 10;
+
+/// How many trailing lines of ffmpeg's stderr are kept for diagnosing failures
+const STDERR_TAIL_LINES: usize = 20;
+
+/// How often the spinner redraws on its own, which also picks up terminal resizes
+const SPINNER_TICK: Duration = Duration::from_millis(100);
+
+/// How a single conversion ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    Succeeded,
+    Failed,
+    /// The user pressed `s`, or another process is already writing the output
+    Skipped,
+}
+
+/// How an input's existing output compares with it, for `--incremental` and `--plan`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputState {
+    /// Nothing has been written for it yet
+    New,
+    /// The source was modified after its output was written
+    Changed,
+    UpToDate,
+}
+
+/// What to do after a conversion failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AfterFailure {
+    /// Convert again, possibly with changed options
+    Retry,
+    /// Give up on this file but carry on with the rest
+    Skip,
+    Quit,
+}
+
+/// A span of the source to extract, in seconds
+#[derive(Debug, Clone, Copy)]
+struct TimeRange {
+    start: f64,
+    end: f64,
+}
 
 /// Main video converter with interactive functionality
 pub struct VideoConverter {
     config: Config,
+    args: Args,
+    formats: Formats,
+    probe_cache: ProbeCache,
+    /// Result lines for `--oneline`
+    oneline: Option<OnelineReport>,
+    /// Canonical paths of every file written so far, so later scans never pick them up as inputs
+    produced: Mutex<HashSet<PathBuf>>,
+    /// Cancels the conversion in progress
+    cancel: CancelToken,
+    /// Cancels just the server job in progress; a child of `cancel`
+    job_cancel: Mutex<Option<CancelToken>>,
 }
 
-impl VideoConverter {
-    pub fn new() -> Self {
-        Self {
-            config: Config::load(),
+impl VideoConverter {
+    pub fn new(args: Args) -> Result<Self, ConverterError> {
+        let config = Config::load(args.config.as_deref())?;
+        symbols::install(config.symbols.clone());
+        let formats = Formats::load(config.formats_file.as_deref())?;
+        let oneline = if args.oneline { Some(OnelineReport::start()?) } else { None };
+        Ok(Self {
+            config,
+            args,
+            formats,
+            probe_cache: ProbeCache::default(),
+            oneline,
+            produced: Mutex::new(HashSet::new()),
+            cancel: CancelToken::new(),
+            job_cancel: Mutex::new(None),
+        })
+    }
+
+    /// Token that stops the conversion in progress (and the rest of a batch) when cancelled
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Token the running conversion stops on: the server job's own, or the run's
+    fn active_cancel_token(&self) -> CancelToken {
+        let job_cancel = self.job_cancel.lock().unwrap_or_else(|e| e.into_inner());
+        job_cancel.clone().unwrap_or_else(|| self.cancel.clone())
+    }
+
+    /// Check if a string is a valid URL
+    fn is_url(input: &str) -> bool {
+        if let Ok(url) = Url::parse(input) {
+            matches!(url.scheme(), "http" | "https")
+        } else {
+            false
+        }
+    }
+
+    /// Sort order from `--sort`, falling back to the configured default
+    fn sort_order(&self) -> SortOrder {
+        match self.args.sort {
+            Some(order) => order,
+            None if self.config.recent_first => SortOrder::Recent,
+            None => SortOrder::Name,
+        }
+    }
+
+    /// Get all video files across the given root directories
+    ///
+    /// Folders and entries that can't be read are skipped and reported as warnings,
+    /// on stderr so `--list` output stays clean.
+    fn get_video_files(&self, roots: &[String]) -> Result<Vec<VideoFile>, Box<dyn std::error::Error>> {
+        let mut video_files = Vec::new();
+        let mut skipped = Vec::new();
+        let mut unsupported = Vec::new();
+        let mut excluded = 0;
+
+        for root in roots {
+            video_files.extend(self.scan_directory(root, &mut skipped, &mut unsupported, &mut excluded));
+        }
+
+        if excluded > 0 {
+            eprintln!("{}", format!("🚫 Excluded {} file(s) in the output directory or written earlier in this run", excluded).blue());
+        }
+
+        if self.args.verbose {
+            Self::report_unsupported(video_files.len(), &unsupported);
+        }
+
+        match self.sort_order() {
+            SortOrder::Name => video_files.sort_by_key(|vf| vf.name.to_lowercase()),
+            SortOrder::Size => video_files.sort_by(|a, b| b.size_mb.total_cmp(&a.size_mb)),
+            SortOrder::Recent => video_files.sort_by_key(|vf| std::cmp::Reverse(vf.modified)),
+        }
+
+        if !skipped.is_empty() {
+            eprintln!("{}", format!("⚠️  Skipped {} unreadable path(s):", skipped.len()).yellow());
+            for path in &skipped {
+                eprintln!("{}", format!("   • {}", path).yellow());
+            }
+        }
+
+        Ok(video_files)
+    }
+
+    /// The `--output-dir`, canonicalized, if it is a subfolder of `root` (the same folder doesn't count)
+    fn nested_output_dir(&self, root: &Path) -> Option<PathBuf> {
+        let output_dir = fs::canonicalize(self.args.output_dir.as_ref()?).ok()?;
+        let root = fs::canonicalize(root).ok()?;
+        (output_dir != root && output_dir.starts_with(&root)).then_some(output_dir)
+    }
+
+    /// Whether `path` is the folder `target`
+    fn is_path(path: &Path, target: Option<&Path>) -> bool {
+        target.is_some_and(|target| fs::canonicalize(path).is_ok_and(|path| path == target))
+    }
+
+    /// Whether this run wrote the file
+    fn was_produced(&self, path: &Path) -> bool {
+        let produced = self.produced.lock().unwrap_or_else(|e| e.into_inner());
+        !produced.is_empty() && fs::canonicalize(path).is_ok_and(|path| produced.contains(&path))
+    }
+
+    /// Remember everything a conversion wrote so later scans skip it
+    fn remember_outputs(&self, output_path: &str, options: &ConversionOptions) {
+        let mut produced = self.produced.lock().unwrap_or_else(|e| e.into_inner());
+        produced.extend(self.all_outputs(output_path, options).iter().filter_map(|file| fs::canonicalize(file).ok()));
+    }
+
+    /// Whether a scanned file counts as a video: by extension, or by content with `--detect-content`
+    fn is_video(&self, video_file: &VideoFile, path: &Path) -> bool {
+        // Empty files have no content to check; they're reported as empty later
+        if !self.args.detect_content || video_file.is_empty() {
+            return video_file.is_supported_video(&self.formats);
+        }
+        // Unreadable files fail later with a proper message, so let them through here
+        match sniff::video_container(path) {
+            Ok(container) => container.is_some(),
+            Err(_) => video_file.is_supported_video(&self.formats),
+        }
+    }
+
+    /// Print how many files were scanned and list the unsupported ones by extension (--verbose)
+    fn report_unsupported(video_count: usize, unsupported: &[PathBuf]) {
+        const SHOWN_PER_EXTENSION: usize = 5;
+
+        eprintln!("{}", format!(
+            "🔍 Scanned {} file(s): {} video, {} unsupported",
+            video_count + unsupported.len(), video_count, unsupported.len()
+        ).blue());
+
+        let mut by_extension: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for path in unsupported {
+            let extension = path
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy().to_lowercase()))
+                .unwrap_or_else(|| "(no extension)".to_string());
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            by_extension.entry(extension).or_default().push(name);
+        }
+
+        for (extension, names) in &by_extension {
+            let mut shown = names.iter().take(SHOWN_PER_EXTENSION).cloned().collect::<Vec<_>>().join(", ");
+            if names.len() > SHOWN_PER_EXTENSION {
+                shown.push_str(&format!(", … and {} more", names.len() - SHOWN_PER_EXTENSION));
+            }
+            eprintln!("{}", format!("   • {} ({}): {}", extension, names.len(), shown).dimmed());
+        }
+    }
+
+    /// Apply the --include/--exclude globs and size limits, returning the kept files and how many were dropped
+    fn filter_video_files(&self, video_files: Vec<VideoFile>) -> (Vec<VideoFile>, usize) {
+        let total = video_files.len();
+        let kept: Vec<VideoFile> = video_files
+            .into_iter()
+            .filter(|f| pattern::is_selected(&f.name, &self.args.includes, &self.args.excludes))
+            .filter(|f| !self.args.min_size.is_some_and(|min| f.size_mb < min))
+            .filter(|f| !self.args.max_size.is_some_and(|max| f.size_mb > max))
+            .collect();
+        let dropped = total - kept.len();
+        (kept, dropped)
+    }
+
+    /// Get all video files in a directory (and its subdirectories in recursive mode)
+    ///
+    /// Anything that can't be read is added to `skipped` as "path: reason", and files
+    /// that aren't supported videos to `unsupported`. The output directory (when it lies
+    /// inside this one) and files this run wrote are left out and counted in `excluded`.
+    fn scan_directory(&self, directory: &str, skipped: &mut Vec<String>, unsupported: &mut Vec<PathBuf>, excluded: &mut usize) -> Vec<VideoFile> {
+        let mut video_files = Vec::new();
+        let dir_path = Path::new(directory);
+
+        if !dir_path.exists() || !dir_path.is_dir() {
+            return video_files;
+        }
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        if self.args.recursive {
+            let output_dir = self.nested_output_dir(dir_path);
+            let mut walker = WalkDir::new(dir_path).into_iter();
+            while let Some(entry) = walker.next() {
+                match entry {
+                    Ok(entry) if entry.file_type().is_dir() && Self::is_path(entry.path(), output_dir.as_deref()) => {
+                        *excluded += WalkDir::new(entry.path())
+                            .into_iter()
+                            .filter_map(Result::ok)
+                            .filter(|e| e.file_type().is_file())
+                            .count();
+                        walker.skip_current_dir();
+                    }
+                    Ok(entry) => paths.push(entry.into_path()),
+                    Err(e) => {
+                        let path = e.path().unwrap_or(dir_path).display().to_string();
+                        let reason = e.io_error().map(|io| io.to_string()).unwrap_or_else(|| e.to_string());
+                        skipped.push(format!("{}: {}", path, reason));
+                    }
+                }
+            }
+        } else {
+            match fs::read_dir(dir_path) {
+                Ok(entries) => {
+                    for entry in entries {
+                        match entry {
+                            Ok(entry) => paths.push(entry.path()),
+                            Err(e) => skipped.push(format!("{}: {}", directory, e)),
+                        }
+                    }
+                }
+                Err(e) => skipped.push(format!("{}: {}", directory, e)),
+            }
+        }
+
+        for path in paths {
+            if path.is_file() {
+                if self.was_produced(&path) {
+                    *excluded += 1;
+                    continue;
+                }
+                match VideoFile::new(&path) {
+                    Ok(mut video_file) => {
+                        if self.is_video(&video_file, &path) {
+                            // Show nested files relative to the root so they stay distinguishable
+                            if let Ok(relative) = path.strip_prefix(dir_path) {
+                                video_file.name = relative.to_string_lossy().to_string();
+                            }
+                            video_file.root = directory.to_string();
+                            video_files.push(video_file);
+                        } else {
+                            unsupported.push(path);
+                        }
+                    }
+                    Err(e) => skipped.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+        }
+
+        video_files
+    }
+
+    /// Turn a stem into a safe output file name, reporting if it had to be shortened
+    fn output_filename(&self, stem: &str, extension: &str) -> String {
+        let (filename, truncated) = fit_filename(&sanitize_filename(stem, self.args.replacement_char), extension);
+        if truncated {
+            println!("{}", format!("⚠️  Output name too long for the filesystem, shortened to: {}", filename).yellow());
+        }
+        filename
+    }
+
+    /// Decoded file stem of a URL's last path segment (`/videos/My%20Lecture.mp4` → `My Lecture`)
+    ///
+    /// The segment is decoded after splitting so an encoded `%2F` can't add folders,
+    /// and the query string is never part of the path.
+    fn url_file_stem(url: &Url) -> Option<String> {
+        let segment = url.path_segments()?.next_back()?;
+        let decoded = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+        let stem = match decoded.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => &decoded,
+        };
+        (!stem.is_empty()).then(|| stem.to_string())
+    }
+
+    /// Generate output audio path based on input path and format
+    ///
+    /// With an output directory configured, files are written there instead of next
+    /// to the source; `--mirror-structure` additionally recreates the source's folder
+    /// layout relative to whichever of `roots` contains it. The stem is sanitized and
+    /// shortened if needed so the name is valid on any filesystem.
+    fn get_output_path(&self, input_path: &str, roots: &[String], options: &ConversionOptions, index: usize) -> String {
+        let extension = options.extension();
+
+        // Remote and pasted inputs have no folder of their own, so they go to the
+        // output directory (or the current directory)
+        let (stem, directory) = if Self::is_url(input_path) || data_uri::is_data_uri(input_path) {
+            let stem = if data_uri::is_data_uri(input_path) {
+                "clip".to_string()
+            } else {
+                Url::parse(input_path)
+                    .ok()
+                    .and_then(|url| Self::url_file_stem(&url))
+                    .unwrap_or_else(|| "output".to_string())
+            };
+            let directory = self.args.output_dir.as_ref().map(PathBuf::from).unwrap_or_default();
+            (stem, directory)
+        } else {
+            let path = Path::new(input_path);
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("output")
+                .to_string();
+
+            let directory = match &self.args.output_dir {
+                Some(output_dir) => {
+                    let mut target = PathBuf::from(output_dir);
+                    if self.args.mirror_structure {
+                        let relative = path
+                            .parent()
+                            .and_then(|parent| roots.iter().find_map(|root| parent.strip_prefix(root).ok()));
+                        if let Some(relative) = relative {
+                            target.push(relative);
+                        }
+                    }
+                    target
+                }
+                None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            };
+            (stem, directory)
+        };
+
+        // Previews get their own name so they never overwrite a real conversion
+        let stem = match options.preview_seconds {
+            Some(_) => format!("{}_preview", stem),
+            None => stem,
+        };
+
+        // The template was validated up front, so it always ends in the right extension
+        if let Some(output_template) = &self.args.output_template {
+            let directory = directory.to_string_lossy();
+            return template::render(
+                output_template,
+                &[
+                    ("name", sanitize_filename(&stem, self.args.replacement_char)),
+                    ("dir", if directory.is_empty() { ".".into() } else { directory.to_string() }),
+                    ("ext", extension.to_string()),
+                    ("bitrate", options.bitrate_kbps.to_string()),
+                    ("format", options.format.name().to_string()),
+                    ("index", format!("{:03}", index)),
+                ],
+            );
+        }
+
+        let filename = self.output_filename(&stem, extension);
+        directory.join(filename).to_string_lossy().to_string()
+    }
+
+    /// Make sure no output of a conversion would land on an existing directory
+    ///
+    /// A derived name can match a folder (say, one called `lecture.mp3`), which ffmpeg only
+    /// reports with a confusing error once it has done all the work. Segments are numbered
+    /// files, so their base name is never written.
+    fn check_output_path(output_path: &str, options: &ConversionOptions) -> Result<(), ConverterError> {
+        let mut outputs: Vec<String> = options
+            .extra_formats
+            .iter()
+            .map(|&format| Self::extra_output_path(output_path, format))
+            .collect();
+        if options.segment_seconds.is_none() {
+            outputs.insert(0, output_path.to_string());
+        }
+        match outputs.into_iter().find(|path| Path::new(path).is_dir()) {
+            Some(path) => Err(ConverterError::OutputIsDirectory { path }),
+            None => Ok(()),
+        }
+    }
+
+    /// Make sure a local input file can be opened before handing it to ffmpeg
+    fn check_readable(input_path: &str) -> Result<(), ConverterError> {
+        match fs::File::open(input_path) {
+            Ok(file) => match file.metadata() {
+                Ok(metadata) if metadata.is_file() && metadata.len() == 0 => Err(ConverterError::EmptyInput {
+                    path: input_path.to_string(),
+                }),
+                _ => Ok(()),
+            },
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => Err(ConverterError::InputUnreadable {
+                path: input_path.to_string(),
+            }),
+            Err(e) => Err(ConverterError::Io(e)),
+        }
+    }
+
+    /// HTTP headers to send when reading this input (only URLs get any)
+    fn headers_for(&self, input_path: &str) -> &[String] {
+        if Self::is_url(input_path) {
+            &self.args.headers
+        } else {
+            &[]
+        }
+    }
+
+    /// Build the ffmpeg argument list for a conversion
+    ///
+    /// A single range is cut with a fast input seek (or a decoding output seek with
+    /// `--accurate-seek`); several ranges are stitched together with an `aselect` filter.
+    fn ffmpeg_args(&self, input_path: &str, output_path: &str, ranges: &[TimeRange], options: &ConversionOptions) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // ffmpeg expects every header line to be terminated by CRLF
+        let headers = self.headers_for(input_path);
+        if !headers.is_empty() {
+            let headers: String = headers.iter().map(|h| format!("{}\r\n", h)).collect();
+            args.extend(["-headers".to_string(), headers]);
+        }
+
+        let input_flags = match options.record_seconds {
+            Some(_) => ffmpeg::recording_input_args(input_path),
+            None => ffmpeg::input_args_for(input_path),
+        };
+        args.extend(input_flags.iter().map(|a| a.to_string()));
+        args.extend(options.input_args.iter().cloned());
+
+        if let Some(hwaccel) = &options.hwaccel {
+            args.extend(["-hwaccel".to_string(), hwaccel.clone()]);
+        }
+        // HLS fetches its keys from the playlist and has no option to override them
+        if let Some(key) = options.decryption_key.as_ref().filter(|_| !ffmpeg::is_hls_playlist(input_path)) {
+            args.extend(["-decryption_key".to_string(), key.clone()]);
+        }
+        if let Some(threads) = options.threads {
+            args.extend(["-threads".to_string(), threads.to_string()]);
+        }
+
+        // -stream_loop counts the extra plays
+        if let Some(count) = options.loop_count.filter(|&count| count > 1) {
+            args.extend(["-stream_loop".to_string(), (count - 1).to_string()]);
+        }
+
+        match ranges {
+            [_] if options.accurate_seek => args.push("-accurate_seek".to_string()),
+            [range] => args.extend(Self::seek_args(range)),
+            _ => {}
+        }
+
+        args.extend(["-i", input_path].map(String::from));
+        args.extend(self.output_spec(output_path, ranges, options));
+
+        // Further formats are encoded from the same decode as extra outputs
+        for &format in &options.extra_formats {
+            let mut extra = options.clone();
+            extra.set_format(format);
+            extra.extra_formats.clear();
+            args.extend(self.output_spec(&Self::extra_output_path(output_path, format), ranges, &extra));
+        }
+
+        args
+    }
+
+    /// `-ss`/`-t` selecting one range
+    fn seek_args(range: &TimeRange) -> [String; 4] {
+        [
+            "-ss".to_string(),
+            format!("{:.3}", range.start),
+            "-t".to_string(),
+            format!("{:.3}", range.end - range.start),
+        ]
+    }
+
+    /// ffmpeg arguments for one output: filters, codec settings, tags and the target
+    fn output_spec(&self, output_path: &str, ranges: &[TimeRange], options: &ConversionOptions) -> Vec<String> {
+        let mut args = vec!["-vn".to_string()];
+        if let [range] = ranges {
+            if options.accurate_seek {
+                args.extend(Self::seek_args(range));
+            }
+        }
+        if let Some(seconds) = options.preview_seconds {
+            args.extend(["-t".to_string(), seconds.to_string()]);
+        }
+        // ffmpeg stops reading at the limit and finalizes the file as usual
+        if let Some(seconds) = options.record_seconds {
+            args.extend(["-t".to_string(), format!("{:.3}", seconds)]);
+        }
+
+        if options.copy_audio {
+            args.extend(["-acodec", "copy"].map(String::from));
+        } else {
+            args.extend(Self::encode_args(ranges, options));
+        }
+
+        // Copy the source's tags first so the explicit ones below replace them. Reproducible
+        // outputs drop them too, since they carry the source's creation time.
+        if options.strip_metadata || options.reproducible {
+            args.extend(["-map_metadata", "-1", "-map_chapters", "-1"].map(String::from));
+        } else if !options.tags.is_empty() {
+            args.extend(["-map_metadata".to_string(), "0".to_string()]);
+        }
+        if !options.tags.is_empty() {
+            for (key, value) in &options.tags {
+                args.extend(["-metadata".to_string(), format!("{}={}", key, value)]);
+            }
+            if options.muxer() == "mp3" {
+                // ID3v2.3 is what most players and car stereos read
+                args.extend(["-id3v2_version".to_string(), "3".to_string()]);
+            }
+        }
+
+        // Leaves out the encoder version tag and anything else that varies between runs
+        if options.reproducible {
+            args.extend(["-fflags", "+bitexact", "-flags", "+bitexact"].map(String::from));
+        }
+
+        args.extend(options.output_args.iter().cloned());
+
+        match options.segment_seconds {
+            Some(seconds) => {
+                args.extend([
+                    "-f".to_string(),
+                    "segment".to_string(),
+                    "-segment_time".to_string(),
+                    seconds.to_string(),
+                    "-segment_format".to_string(),
+                    options.muxer().to_string(),
+                    "-segment_start_number".to_string(),
+                    "1".to_string(),
+                    "-reset_timestamps".to_string(),
+                    "1".to_string(),
+                    "-y".to_string(),
+                    Self::segment_pattern(output_path),
+                ]);
+            }
+            None => {
+                args.extend(options.container_args().iter().map(|a| a.to_string()));
+                args.extend(["-y", output_path].map(String::from));
+            }
+        }
+
+        args
+    }
+
+    /// Filters and encoder settings for an output that's encoded rather than copied
+    fn encode_args(ranges: &[TimeRange], options: &ConversionOptions) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Range selection has to run before any other filter, and ffmpeg only takes one -af
+        let mut filters = Vec::new();
+        if ranges.len() > 1 {
+            let selection: Vec<String> = ranges
+                .iter()
+                .map(|r| format!("between(t,{:.3},{:.3})", r.start, r.end))
+                .collect();
+            filters.push(format!("aselect='{}',asetpts=N/SR/TB", selection.join("+")));
+        }
+        filters.extend(options.audio_filters());
+        if !filters.is_empty() {
+            args.extend(["-af".to_string(), filters.join(",")]);
+        }
+
+        args.extend(["-acodec".to_string(), options.encoder.clone()]);
+        if let Some(threads) = options.threads {
+            args.extend(["-threads".to_string(), threads.to_string()]);
+        }
+        if !options.format.is_lossless() {
+            args.extend(["-ab".to_string(), format!("{}k", options.bitrate_kbps)]);
+        }
+        if let Some(channels) = options.channel_count() {
+            args.extend(["-ac".to_string(), channels.to_string()]);
+        }
+        if let Some(sample_rate) = options.sample_rate {
+            args.extend(["-ar".to_string(), sample_rate.to_string()]);
+        }
+        if options.gapless && options.encoder == "libmp3lame" {
+            // Without the bit reservoir every frame decodes on its own, so a cut between parts can't click
+            args.extend(["-reservoir".to_string(), "0".to_string()]);
+            // The LAME header tells players how much encoder delay and padding to trim
+            if options.segment_seconds.is_none() && !options.compat {
+                args.extend(["-write_xing".to_string(), "1".to_string()]);
+            }
+        }
+        if options.compat && options.encoder == "libmp3lame" {
+            // Plain left/right stereo; some decoders mishandle joint stereo frames
+            args.extend(["-joint_stereo".to_string(), "0".to_string()]);
+            // A bare CBR stream, without the Xing/LAME header some players choke on
+            if options.segment_seconds.is_none() {
+                args.extend(["-write_xing".to_string(), "0".to_string()]);
+            }
+        }
+
+        args
+    }
+
+    /// Where an additional `--formats` output goes: the main output with that format's extension
+    fn extra_output_path(output_path: &str, format: AudioFormat) -> String {
+        Path::new(output_path)
+            .with_extension(format.extension())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Every file a conversion wrote: the output (or its segments) and any extra formats
+    fn all_outputs(&self, output_path: &str, options: &ConversionOptions) -> Vec<String> {
+        let mut files = self.produced_files(output_path, options);
+        for &format in &options.extra_formats {
+            let mut extra = options.clone();
+            extra.set_format(format);
+            files.extend(self.produced_files(&Self::extra_output_path(output_path, format), &extra));
+        }
+        files.retain(|file| Path::new(file).exists());
+        files
+    }
+
+    /// Pack a batch's outputs into the --archive file, removing the loose files once it is written
+    async fn archive_outputs(&self, archive_path: &str, files: &[String]) {
+        if files.is_empty() {
+            println!("{}", "ℹ️  Nothing was converted, so no archive was written".blue());
+            return;
+        }
+
+        let pb = Self::spinner();
+        pb.set_message(format!("📦 Packing {} file(s) into {}...", files.len(), archive_path));
+        let result = archive::create(archive_path, files).await;
+        pb.finish_and_clear();
+
+        match result {
+            Ok(()) => {
+                for file in files {
+                    let _ = fs::remove_file(file);
+                }
+                let size = self.get_file_size_mb(archive_path).map(Self::format_size).unwrap_or_else(|| "unknown size".to_string());
+                println!("{}", format!("📦 Archive: {} ({}, {} file(s))", archive_path, size, files.len()).green());
+            }
+            Err(e) => println!("{}", format!("⚠️  Could not write {} ({}), the converted files were left in place", archive_path, e).yellow()),
+        }
+    }
+
+    /// Print each additional `--formats` output with its size
+    fn report_extra_outputs(&self, output_path: &str, options: &ConversionOptions) {
+        for &format in &options.extra_formats {
+            let extra_path = Self::extra_output_path(output_path, format);
+            let mut extra = options.clone();
+            extra.set_format(format);
+            match self.output_size_mb(&extra_path, &extra) {
+                Some(size_mb) => println!("{}", format!("💾 Also saved {}: {} ({:.2} MB)", format, extra_path, size_mb).green()),
+                None => println!("{}", format!("⚠️  {} output is missing: {}", format, extra_path).yellow()),
+            }
+        }
+    }
+
+    /// ffmpeg filename pattern for segmented output: `<stem>_%03d.<ext>`
+    fn segment_pattern(output_path: &str) -> String {
+        let path = Path::new(output_path);
+        // A literal '%' in the name would be read as a pattern placeholder
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output")
+            .replace('%', "%%");
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+        let filename = format!("{}_%03d.{}", stem, extension);
+
+        match path.parent() {
+            Some(parent) => parent.join(filename).to_string_lossy().to_string(),
+            None => filename,
+        }
+    }
+
+    /// Files a finished conversion produced: the output itself, or each numbered segment
+    fn produced_files(&self, output_path: &str, options: &ConversionOptions) -> Vec<String> {
+        if options.segment_seconds.is_none() {
+            return vec![output_path.to_string()];
+        }
+
+        let path = Path::new(output_path);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+        let parent = path.parent().unwrap_or(Path::new(""));
+
+        (1..)
+            .map(|n| parent.join(format!("{}_{:03}.{}", stem, n, extension)))
+            .take_while(|segment| segment.exists())
+            .map(|segment| segment.to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// Whether an input still needs converting, judged by modification times
+    fn output_state(&self, video_file: &VideoFile, output_path: &str, options: &ConversionOptions) -> OutputState {
+        // Segments are judged by the first one
+        let Some(first) = self.produced_files(output_path, options).into_iter().next() else {
+            return OutputState::New;
+        };
+        // A directory of that name isn't an output; converting reports it
+        let Some(output_modified) = fs::metadata(first).ok().filter(|m| m.is_file()).and_then(|m| m.modified().ok()) else {
+            return OutputState::New;
+        };
+        match video_file.modified {
+            Some(source_modified) if source_modified > output_modified => OutputState::Changed,
+            _ => OutputState::UpToDate,
+        }
+    }
+
+    /// Combined size of everything a conversion produced
+    fn output_size_mb(&self, output_path: &str, options: &ConversionOptions) -> Option<f64> {
+        self.produced_files(output_path, options)
+            .iter()
+            .map(|file| self.get_file_size_mb(file))
+            .sum()
+    }
+
+    /// Header names with their values hidden, safe to print
+    fn redacted_headers(&self) -> Vec<String> {
+        self.args.headers.iter().map(|h| Self::redact_header(h)).collect()
+    }
+
+    /// A header's name with its value hidden: `Authorization: <redacted>`
+    fn redact_header(header: &str) -> String {
+        match header.split_once(':') {
+            Some((name, _)) => format!("{}: <redacted>", name),
+            None => "<redacted>".to_string(),
+        }
+    }
+
+    /// The ffmpeg command line with header values and the decryption key hidden, safe to log
+    fn redacted_command(args: &[String]) -> String {
+        let mut redacted = Vec::with_capacity(args.len());
+        let mut previous: Option<&str> = None;
+        for arg in args {
+            redacted.push(match previous {
+                Some("-headers") => arg
+                    .split("\r\n")
+                    .filter(|h| !h.is_empty())
+                    .map(Self::redact_header)
+                    .collect::<Vec<_>>()
+                    .join("\\r\\n"),
+                Some("-decryption_key") => "<redacted>".to_string(),
+                _ => arg.clone(),
+            });
+            previous = Some(arg);
+        }
+        format!("ffmpeg {}", redacted.join(" "))
+    }
+
+    /// Create the conversion spinner, falling back to a hidden no-op bar if the style can't be built
+    ///
+    /// The message is cut to the terminal width (`wide_msg`) and redrawn on a steady
+    /// tick, so resizing mid-conversion can't leave wrapped fragments behind. The guard
+    /// clears the spinner when dropped, including while unwinding from a panic.
+    fn spinner() -> SpinnerGuard {
+        if progress::spinners_hidden() {
+            return SpinnerGuard::new(ProgressBar::hidden());
+        }
+        let bar = match ProgressStyle::default_spinner().template("{spinner:.green} {wide_msg}") {
+            Ok(style) => {
+                let pb = ProgressBar::new_spinner();
+                pb.set_style(style);
+                pb.enable_steady_tick(SPINNER_TICK);
+                pb
+            }
+            Err(_) => ProgressBar::hidden(),
+        };
+        SpinnerGuard::new(bar)
+    }
+
+    /// Length of the audio a conversion will produce, for progress reporting
+    async fn expected_duration(&self, input_path: &str, ranges: &[TimeRange]) -> Option<f64> {
+        if !ranges.is_empty() {
+            return Some(ranges.iter().map(|r| r.end - r.start).sum());
+        }
+
+        self.probe_cache
+            .probe(input_path, self.headers_for(input_path))
+            .await
+            .ok()?
+            .duration
+    }
+
+    /// Convert video to the chosen audio format using ffmpeg
+    ///
+    /// Failures that match a known recoverable ffmpeg error are retried once per remedy,
+    /// with the corrective flags added. `on_progress` is told about every progress update.
+    async fn convert_to_mp3(&self, input_path: &str, output_path: &str, ranges: &[TimeRange], options: &ConversionOptions, on_progress: Option<ProgressCallback>) -> Result<Conversion, ConverterError> {
+        if !Self::is_url(input_path) {
+            Self::check_readable(input_path)?;
+        }
+        Self::check_output_path(output_path, options)?;
+
+        // Create the output folder (and any mirrored subfolders) if needed
+        if let Some(parent) = Path::new(output_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        // Another convert process writing the same file would clobber this one's output
+        let _lock = match OutputLock::acquire(output_path)? {
+            Ok(lock) => lock,
+            Err(pid) => {
+                println!("{}", symbols::line(Status::Skip, format!(
+                    "Skipping: {} is being written by another convert process (PID {})",
+                    output_path, pid
+                )).yellow());
+                return Ok(Conversion::Skipped);
+            }
+        };
+
+        println!("{}", symbols::line(Status::Convert, format!("Converting: {} → {}", 
+            Path::new(input_path).file_name().unwrap_or_default().to_string_lossy(),
+            Path::new(output_path).file_name().unwrap_or_default().to_string_lossy()
+        )).blue());
+
+        let staged = self.handle_network_input(input_path).await?;
+        let staged_path;
+        let input_path = match &staged {
+            Some(staged) => {
+                staged_path = staged.path_str();
+                staged_path.as_str()
+            }
+            None => input_path,
+        };
+
+        // Write beside the output and rename once complete, so the output path never holds a
+        // half-written file (and ffmpeg never reads and writes the same one). `<stem>.part.<ext>`
+        // keeps the extension ffmpeg picks the muxer from. Segments are numbered files written
+        // in place, as there's no single file to swap in.
+        if Self::is_same_file(input_path, output_path) {
+            println!("{}", "ℹ️  Output would overwrite the input, it is replaced once the conversion succeeds".blue());
+        }
+        let target_path = match options.segment_seconds {
+            None => Self::with_stem_suffix(output_path, ".part"),
+            Some(_) => output_path.to_string(),
+        };
+
+        let looped_seconds = self
+            .expected_duration(input_path, ranges)
+            .await
+            .map(|seconds| seconds * f64::from(options.loop_count.unwrap_or(1)));
+        // A live stream has no length, so the progress bar counts up to the recording limit instead
+        let limit = options.preview_seconds.map(f64::from).or(options.record_seconds);
+        let total_seconds = match (looped_seconds, limit) {
+            (Some(total), Some(limit)) => Some(total.min(limit)),
+            (total, limit) => total.or(limit),
+        };
+
+        if !self.within_duration_limit(total_seconds)? {
+            println!("{}", symbols::line(Status::Skip, "Skipped: longer than --max-duration").yellow());
+            return Ok(Conversion::Skipped);
+        }
+
+        if !self.passes_integrity_check(input_path).await {
+            println!("{}", symbols::line(Status::Skip, "Skipped: the input looks truncated or corrupt (--force converts it anyway)").yellow());
+            return Ok(Conversion::Skipped);
+        }
+
+        if let Some(count) = options.loop_count.filter(|&count| count > 1) {
+            Self::report_loop(count, total_seconds, options);
+        }
+
+        if ffmpeg::is_streaming_manifest(input_path) {
+            match total_seconds {
+                Some(total) => println!("{}", format!(
+                    "📡 HLS/DASH stream of {}, reading with reconnects enabled",
+                    probe::format_timestamp(total)
+                ).blue()),
+                None => println!("{}", "📡 HLS/DASH stream of unknown length (live?), reading with reconnects enabled".blue()),
+            }
+            if options.decryption_key.is_some() && ffmpeg::is_hls_playlist(input_path) {
+                println!("{}", "ℹ️  HLS keys are fetched from the URIs in the playlist (with your --header values), so --key isn't used".blue());
+            }
+        }
+        if let (Some(_), Some(total)) = (options.record_seconds, total_seconds) {
+            println!("{}", format!("🔴 Recording {} of audio, then stopping", probe::format_timestamp(total)).blue());
+        }
+
+        let mut options = options.clone();
+        if self.args.keep_logs.is_some() {
+            // Every attempt appends to it, so start from an empty log
+            let file_name = Path::new(output_path).file_name().unwrap_or_default().to_string_lossy();
+            let log_path = naming::sibling_path(output_path, &file_name, "log");
+            let _ = fs::remove_file(&log_path);
+            options.log_path = Some(log_path);
+        }
+        let info = self.probe_cache.probe(input_path, self.headers_for(input_path)).await.ok();
+        if let Some(audio) = info.as_ref().and_then(|info| info.audio()) {
+            if self.should_smart_copy(&options, audio, ranges) {
+                options.copy_audio = true;
+            }
+        }
+
+        // A copied stream keeps its channels and timestamps, and can't be filtered anyway
+        if !options.copy_audio {
+            if let Some(audio) = info.as_ref().and_then(|info| info.audio()) {
+                self.handle_surround(&mut options, audio);
+            }
+
+            if info.as_ref().is_some_and(|info| info.variable_frame_rate) {
+                println!("{}", "🎞️  Variable frame rate source: resampling the audio against its timestamps (aresample=async=1) to keep it in sync".yellow());
+                options.resync_audio = true;
+            }
+        }
+
+        if options.safe_mode {
+            let demuxer = info.as_ref().and_then(|info| info.demuxer.as_deref());
+            Self::apply_safe_mode(&mut options, demuxer);
+        }
+
+        if self.args.replaygain {
+            self.add_replaygain_tags(input_path, &mut options).await;
+        }
+
+        let mut applied = Vec::new();
+        let (status, stderr_tail) = loop {
+            let attempt = self.run_ffmpeg(input_path, &target_path, ranges, &options, total_seconds, on_progress.clone()).await;
+            if let Err(ConverterError::Cancelled) = attempt {
+                self.remove_partial_output(&target_path, &options);
+                Self::remove_log(&options);
+                let message = if self.args.serve.is_some() {
+                    "🛑 Job cancelled (partial output removed)"
+                } else {
+                    "🛑 Cancelled, no further files will be converted (partial output removed)"
+                };
+                println!("\n{}", message.red());
+                return Err(ConverterError::Cancelled);
+            }
+            let Some((status, stderr_tail)) = attempt? else {
+                self.remove_partial_output(&target_path, &options);
+                Self::remove_log(&options);
+                println!("{}", symbols::line(Status::Skip, format!(
+                    "Skipped {} (partial output removed)",
+                    Path::new(input_path).file_name().unwrap_or_default().to_string_lossy()
+                )).yellow());
+                return Ok(Conversion::Skipped);
+            };
+            if status.success() {
+                break (status, stderr_tail);
+            }
+
+            match recovery::diagnose(&stderr_tail, &applied) {
+                Some(remedy) => {
+                    println!("{}", format!(
+                        "🔧 FFmpeg reported {}, retrying with {}",
+                        remedy.problem,
+                        remedy.flags().join(" ")
+                    ).yellow());
+                    options.input_args.extend(remedy.input_args.iter().map(|a| a.to_string()));
+                    options.output_args.extend(remedy.output_args.iter().map(|a| a.to_string()));
+                    applied.push(remedy.problem);
+                }
+                None => break (status, stderr_tail),
+            }
+        };
+
+        if target_path != output_path {
+            // ffmpeg can exit cleanly after writing nothing, e.g. when every stream was filtered out
+            if status.success() && !fs::metadata(&target_path).is_ok_and(|m| m.len() > 0) {
+                self.remove_partial_output(&target_path, &options);
+                println!("{}", symbols::line(Status::Failure, "FFmpeg finished without writing any audio, so there is no output").red());
+                return Ok(Conversion::Failed);
+            }
+
+            let extra_paths = options.extra_formats.iter().map(|&format| {
+                (Self::extra_output_path(&target_path, format), Self::extra_output_path(output_path, format))
+            });
+            if status.success() {
+                fs::rename(&target_path, output_path)?;
+                for (temp, extra) in extra_paths {
+                    fs::rename(temp, extra)?;
+                }
+            } else {
+                self.remove_partial_output(&target_path, &options);
+            }
+        }
+
+        if status.success() && self.args.keep_logs != Some(KeepLogs::Always) {
+            Self::remove_log(&options);
+        }
+        let report_log = || {
+            if let Some(log_path) = options.log_path.as_ref().filter(|path| Path::new(path).exists()) {
+                println!("{}", format!("📝 Full FFmpeg log: {}", log_path).blue());
+            }
+        };
+
+        if status.success() {
+            println!("{}", symbols::line(Status::Success, "Conversion completed successfully!").green());
+            if options.copy_audio {
+                println!("{}", "📦 Copied without re-encoding".green());
+            }
+            report_log();
+            self.remember_outputs(output_path, &options);
+            self.report_actual_output(output_path, &options).await;
+            Ok(Conversion::Succeeded)
+        } else {
+            // A killed ffmpeg is reported by the caller, as `ConverterError::Killed`
+            let signal = ffmpeg::termination_signal(&status);
+            match (status.code(), signal) {
+                (Some(code), _) => println!("{}", symbols::line(Status::Failure, format!("FFmpeg failed with exit code: {}", code)).red()),
+                (None, Some(_)) => {}
+                (None, None) => println!("{}", symbols::line(Status::Failure, "FFmpeg process was terminated").red()),
+            }
+            if !stderr_tail.is_empty() {
+                println!("{}", "📝 FFmpeg output:".yellow());
+                for line in &stderr_tail {
+                    println!("   {}", line.dimmed());
+                }
+            }
+            report_log();
+            if recovery::is_encryption_failure(&stderr_tail) {
+                println!("{}", match ffmpeg::is_hls_playlist(input_path) {
+                    true => "🔑 The stream is encrypted and its key couldn't be fetched; the key URL may need the same --header values (cookies, tokens) as the playlist",
+                    false => "🔑 The input is encrypted; pass its 128-bit key with --key <HEX>",
+                }.yellow());
+            }
+            if let Some(signal) = signal {
+                if let Some(explanation) = recovery::explain_kill(signal) {
+                    println!("{}", explanation.yellow());
+                }
+                return Err(ConverterError::Killed { signal });
+            }
+            Ok(Conversion::Failed)
+        }
+    }
+
+    /// Delete the `--keep-logs` log of a conversion that doesn't need one
+    fn remove_log(options: &ConversionOptions) {
+        if let Some(log_path) = &options.log_path {
+            let _ = fs::remove_file(log_path);
+        }
+    }
+
+    /// Open the `--keep-logs` log for one ffmpeg attempt, starting with the command line
+    ///
+    /// Attempts retried with recovery flags append to the same log. Header values and the
+    /// decryption key are left out of the logged command. A log that can't be written is
+    /// reported and left out; it never stops the conversion.
+    async fn open_log(log_path: &str, args: &[String]) -> Option<tokio::fs::File> {
+        let opened = async {
+            let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(log_path).await?;
+            file.write_all(format!("$ {}\n", Self::redacted_command(args)).as_bytes()).await?;
+            Ok::<_, std::io::Error>(file)
+        };
+        match opened.await {
+            Ok(file) => Some(file),
+            Err(e) => {
+                println!("{}", format!("⚠️  Couldn't write the log {}: {}", log_path, e).yellow());
+                None
+            }
+        }
+    }
+
+    /// Whether to copy a source whose audio already is what the conversion would produce
+    ///
+    /// The codec has to match and the bitrate can't be more than 5% above the requested one
+    /// (re-encoding to a higher bitrate gains nothing). Anything that needs the samples
+    /// changed, such as filters, a new channel count or sample rate, rules copying out.
+    fn should_smart_copy(&self, options: &ConversionOptions, audio: &AudioStream, ranges: &[TimeRange]) -> bool {
+        if options.smart_copy == SmartCopy::Off || options.copy_audio {
+            return false;
+        }
+
+        let codec = options.format.codec_name();
+        let bitrate = audio.bitrate_kbps;
+        let matches = audio.codec.as_deref() == Some(codec)
+            && options.container.iter().all(|container| container.holds_codec(codec))
+            && (options.format.is_lossless() || bitrate.is_some_and(|kbps| kbps <= options.bitrate_kbps + options.bitrate_kbps / 20))
+            && ranges.len() <= 1
+            && options.audio_filters().is_empty()
+            && options.channel_count().map_or(audio.channels <= 2, |channels| channels == audio.channels)
+            && options.sample_rate.iter().all(|&rate| audio.sample_rate == Some(rate))
+            && !options.compat
+            && !options.gapless;
+        if !matches {
+            return false;
+        }
+
+        let description = match bitrate {
+            Some(kbps) if !options.format.is_lossless() => format!("{} at {} kbps", options.format, kbps),
+            _ => options.format.to_string(),
+        };
+        match options.smart_copy {
+            SmartCopy::Auto => {
+                println!("{}", format!("📦 Source audio is already {}, copying it instead of re-encoding", description).blue());
+                true
+            }
+            _ => Confirm::new(&format!("The source audio is already {}. Copy it without re-encoding?", description))
+                .with_default(true)
+                .with_help_message("re-encoding would only lose quality")
+                .prompt()
+                .unwrap_or(false),
+        }
+    }
+
+    /// Point out inputs on a network share, copying them to local disk with `--stage-locally`
+    async fn handle_network_input(&self, input_path: &str) -> Result<Option<StagedInput>, ConverterError> {
+        if Self::is_url(input_path) {
+            return Ok(None);
+        }
+        let Some(kind) = remote::network_share(input_path) else {
+            return Ok(None);
+        };
+
+        if !self.args.stage_locally {
+            println!("{}", format!(
+                "🌐 Source is on a {} network share; if progress stalls, it's waiting on the network (--stage-locally copies it first)",
+                kind
+            ).blue());
+            return Ok(None);
+        }
+
+        let pb = Self::spinner();
+        pb.set_message(format!("📥 Copying from the {} share to local disk...", kind));
+        let staged = remote::stage_locally(input_path).await?;
+        pb.finish_and_clear();
+        println!("{}", format!("📥 Copied from the {} share to {}", kind, staged.path.display()).blue());
+        Ok(Some(staged))
+    }
+
+    /// Say how long (and, for lossy formats, how big) a `--loop` output will get
+    fn report_loop(count: u32, total_seconds: Option<f64>, options: &ConversionOptions) {
+        let Some(seconds) = total_seconds else {
+            println!("{}", format!("🔁 Looping the audio {} times (length unknown)", count).yellow());
+            return;
+        };
+
+        let mut message = format!("🔁 Looping the audio {} times: the output will be {} long", count, probe::format_timestamp(seconds));
+        if !options.format.is_lossless() && !options.copy_audio {
+            let size_mb = f64::from(options.bitrate_kbps) * seconds / 8.0 / 1024.0;
+            message.push_str(&format!(", about {}", Self::format_size(size_mb)));
+        }
+        println!("{}", message.yellow());
+    }
+
+    /// Check `--max-duration`, asking whether to go ahead when someone is at the terminal
+    ///
+    /// Unattended runs (batch, server, no terminal) fail with `TooLong` instead of asking.
+    fn within_duration_limit(&self, total_seconds: Option<f64>) -> Result<bool, ConverterError> {
+        let (Some(limit), Some(duration)) = (self.args.max_duration, total_seconds) else {
+            return Ok(true);
+        };
+        if duration <= limit || self.args.force {
+            return Ok(true);
+        }
+
+        let interactive = !self.args.batch && self.args.serve.is_none() && std::io::stdin().is_terminal();
+        if !interactive {
+            return Err(ConverterError::TooLong { duration, limit });
+        }
+
+        let question = format!(
+            "This input is {} long, over the {} limit. Convert it anyway?",
+            probe::format_timestamp(duration),
+            probe::format_timestamp(limit)
+        );
+        Ok(Confirm::new(&question).with_default(false).prompt().unwrap_or(false))
+    }
+
+    /// With `--check-integrity`, whether a local input's end decodes cleanly enough to convert it
+    ///
+    /// A flagged file is converted anyway with `--force` or when the user says so; unattended
+    /// runs skip it. Streams and URLs are never checked.
+    async fn passes_integrity_check(&self, input_path: &str) -> bool {
+        if !self.args.check_integrity || Self::is_url(input_path) || !Path::new(input_path).is_file() {
+            return true;
+        }
+
+        let reason = {
+            let pb = Self::spinner();
+            pb.set_message("🩺 Checking the end of the file...");
+            ffmpeg::check_tail(input_path, INTEGRITY_TAIL_SECONDS).await
+        };
+        let Some(reason) = reason else {
+            return true;
+        };
+        println!("{}", format!("🩺 The input looks truncated or corrupt (a partial download?): {}", reason).yellow());
+        if self.args.force {
+            println!("{}", "ℹ️  Converting it anyway (--force)".blue());
+            return true;
+        }
+
+        let interactive = !self.args.batch && self.args.serve.is_none() && std::io::stdin().is_terminal();
+        interactive && Confirm::new("Convert it anyway?").with_default(false).prompt().unwrap_or(false)
+    }
+
+    /// Probe what was written and print the codec, bitrate and layout it really has
+    async fn report_actual_output(&self, output_path: &str, options: &ConversionOptions) {
+        // Segments share their settings, so the first one speaks for all
+        let Some(produced) = self.produced_files(output_path, options).into_iter().next() else {
+            return;
+        };
+        let description = self.probe_cache.probe(&produced, &[]).await.ok().and_then(|info| probe::describe_audio(&info));
+        if let Some(description) = description {
+            println!("{}", format!("📋 Output: {}", description).blue());
+        }
+
+        // The server sends the fingerprint with its finished event instead
+        if self.args.fingerprint && self.args.serve.is_none() {
+            self.print_fingerprint(&produced).await;
+        }
+    }
+
+    /// Compute and print `--fingerprint` for a file
+    async fn print_fingerprint(&self, path: &str) {
+        let pb = Self::spinner();
+        pb.set_message("🔑 Fingerprinting the audio...");
+        let fingerprint = fingerprint::compute(path).await;
+        pb.finish_and_clear();
+        match fingerprint {
+            Some(fingerprint) => println!("{}", format!("🔑 Fingerprint: {}", fingerprint).blue()),
+            None => println!("{}", "⚠️  Could not fingerprint the audio".yellow()),
+        }
+    }
+
+    /// Delete whatever a cancelled ffmpeg run had written so far
+    fn remove_partial_output(&self, target_path: &str, options: &ConversionOptions) {
+        for file in self.produced_files(target_path, options) {
+            let _ = fs::remove_file(file);
+        }
+        for &format in &options.extra_formats {
+            let _ = fs::remove_file(Self::extra_output_path(target_path, format));
+        }
+    }
+
+    /// Switch to the conservative `--safe-mode` decode and say which flags it added
+    fn apply_safe_mode(options: &mut ConversionOptions, demuxer: Option<&str>) {
+        let mut flags: Vec<String> = recovery::SAFE_MODE_INPUT_ARGS.iter().map(|a| a.to_string()).collect();
+        // Name the demuxer ffprobe detected so ffmpeg doesn't guess again from damaged data
+        if let Some(demuxer) = demuxer {
+            flags.extend(["-f".to_string(), demuxer.to_string()]);
+        }
+
+        println!("{}", format!("🛟 Safe mode: software decode with {}", flags.join(" ")).yellow());
+        options.hwaccel = None;
+        options.input_args.extend(flags);
+    }
+
+    /// Measure the input's loudness and add ReplayGain tags to the output
+    async fn add_replaygain_tags(&self, input_path: &str, options: &mut ConversionOptions) {
+        let pb = Self::spinner();
+        pb.set_message("📏 Measuring loudness for ReplayGain...");
+        let measured = loudness::measure(input_path, self.headers_for(input_path)).await;
+        pb.finish_and_clear();
+
+        match measured {
+            Some(loudness) => {
+                let tags = loudness.replaygain_tags();
+                println!("{}", format!(
+                    "📏 Loudness {:.1} LUFS, peak {:.1} dBTP → ReplayGain {}",
+                    loudness.integrated_lufs, loudness.true_peak_db, tags[0].1
+                ).blue());
+                options.tags.extend(tags);
+            }
+            None => println!("{}", "⚠️  Could not measure loudness, skipping ReplayGain tags".yellow()),
+        }
+    }
+
+    /// Decide between keeping and downmixing the channels of a surround source
+    ///
+    /// Sources are downmixed to stereo unless more channels were asked for and the
+    /// format can carry them.
+    fn handle_surround(&self, options: &mut ConversionOptions, audio: &AudioStream) {
+        if audio.channels <= 2 {
+            return;
+        }
+
+        let layout = audio.channel_layout.clone().unwrap_or_else(|| format!("{} channels", audio.channels));
+        let wants_surround = !options.downmix_mono
+            && (options.channels.is_some_and(|c| c > 2) || (self.args.keep_surround && options.channels.is_none()));
+
+        if wants_surround {
+            if options.format.max_channels() >= audio.channels {
+                println!("{}", format!("🔊 Keeping all {} channels of the {} source", audio.channels, layout).blue());
+                return;
+            }
+            println!("{}", format!(
+                "⚠️  {} can't hold {} channels, downmixing anyway",
+                options.format, audio.channels
+            ).yellow());
+        }
+
+        let target = if options.downmix_mono || options.channels == Some(1) { "mono" } else { "stereo" };
+        println!("{}", format!(
+            "⚠️  Source is {} surround; downmixing to {} folds the surround channels in{}",
+            layout,
+            target,
+            if options.format.max_channels() > 2 { " (use --keep-surround to keep them)" } else { "" }
+        ).yellow());
+
+        match audio.channel_layout.as_deref().and_then(options::stereo_downmix_filter) {
+            Some(filter) => options.surround_filter = Some(filter),
+            // Unknown layout: fall back to ffmpeg's own downmix
+            None if options.channels.is_none() => options.channels = Some(2),
+            None => {}
+        }
+    }
+
+    /// Run one ffmpeg attempt with progress display and key handling
+    ///
+    /// Returns the exit status along with the last lines ffmpeg wrote to stderr,
+    /// or `None` when the user pressed `s` to skip the file.
+    async fn run_ffmpeg(&self, input_path: &str, target_path: &str, ranges: &[TimeRange], options: &ConversionOptions, total_seconds: Option<f64>, on_progress: Option<ProgressCallback>) -> Result<Option<(ExitStatus, Vec<String>)>, ConverterError> {
+        let pb = Self::spinner();
+        pb.set_message("🎬 FFmpeg started...");
+
+        // Keep ffmpeg off the terminal's stdin so our key handling gets every keypress,
+        // and have it report progress as key=value lines on stdout
+        let mut args: Vec<String> = ["-nostdin", "-hide_banner", "-progress", "pipe:1", "-nostats"].map(String::from).to_vec();
+        args.extend(self.ffmpeg_args(input_path, target_path, ranges, options));
+        let log = match &options.log_path {
+            Some(log_path) => Self::open_log(log_path, &args).await,
+            None => None,
+        };
+
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(niceness) = self.args.nice {
+            ffmpeg::lower_priority(&mut command, niceness);
+        }
+        let spawned = command.spawn();
+
+        let mut cmd = match spawned {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                pb.finish_and_clear();
+                return Err(ConverterError::FfmpegNotFound);
+            }
+            Err(e) => {
+                pb.finish_and_clear();
+                return Err(e.into());
+            }
+        };
+
+        // Keep only the tail of stderr; that's where ffmpeg explains a failure. The rest
+        // goes to the log, if there is one.
+        let stderr_tail = cmd
+            .stderr
+            .take()
+            .map(|stderr| tokio::spawn(lines::tail(BufReader::new(stderr), STDERR_TAIL_LINES, log)));
+
+        // The server has no keyboard of its own; its jobs are cancelled by the front-end
+        let mut keys = if self.args.serve.is_some() { None } else { KeyListener::start() };
+        let cancel = self.active_cancel_token();
+        let pause_hint = if keys.is_some() { " (p to pause, s to skip, Ctrl+C to cancel all)" } else { "" };
+        let converting = format!("Converting... Please wait{}", pause_hint);
+        pb.set_message(converting.clone());
+
+        // Show percentage, speed and ETA as ffmpeg reports progress
+        if let Some(stdout) = cmd.stdout.take() {
+            let pb = ProgressBar::clone(&pb);
+            let mut tracker = ProgressTracker::new(total_seconds);
+            let mut lines = BoundedLines::new(BufReader::new(stdout));
+
+            tokio::spawn(async move {
+                while let Some(line) = lines.next_line().await {
+                    if tracker.update(&line) {
+                        pb.set_message(format!("Converting... {}{}", tracker.message(), pause_hint));
+                        if let Some(on_progress) = &on_progress {
+                            on_progress(&tracker);
+                        }
+                    }
+                }
+            });
+        }
+
+        let mut paused = false;
+        let status = loop {
+            let key = async {
+                match keys.as_mut() {
+                    Some(listener) => listener.next().await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                status = cmd.wait() => break status?,
+                Some(command) = key => match command {
+                    KeyCommand::TogglePause => {
+                        if let Some(pid) = cmd.id() {
+                            if ffmpeg::set_paused(pid, !paused) {
+                                paused = !paused;
+                                pb.set_message(if paused { "⏸️  Paused — press p to resume".to_string() } else { converting.clone() });
+                            }
+                        }
+                    }
+                    KeyCommand::Skip => {
+                        let _ = cmd.kill().await;
+                        return Ok(None);
+                    }
+                    // Raw mode swallows the SIGINT, so Ctrl+C arrives here as a key
+                    KeyCommand::Interrupt => self.cancel.cancel(),
+                },
+                _ = cancel.cancelled() => {
+                    let _ = cmd.kill().await;
+                    drop(keys.take());
+                    pb.finish_and_clear();
+                    return Err(ConverterError::Cancelled);
+                }
+            }
+        };
+        drop(keys);
+        pb.finish_and_clear();
+
+        let stderr_tail = match stderr_tail {
+            Some(handle) => handle.await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        Ok(Some((status, stderr_tail)))
+    }
+
+    /// Check whether two paths refer to the same existing file
+    fn is_same_file(a: &str, b: &str) -> bool {
+        match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Sibling path with a suffix added to the stem: `<stem><suffix>.<ext>`, shortened if too long
+    fn with_stem_suffix(output_path: &str, suffix: &str) -> String {
+        let path = Path::new(output_path);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+        naming::sibling_path(output_path, &format!("{}{}", stem, suffix), extension)
+    }
+
+    /// Get file size in MB
+    fn get_file_size_mb(&self, file_path: &str) -> Option<f64> {
+        if let Ok(metadata) = fs::metadata(file_path) {
+            Some(metadata.len() as f64 / (1024.0 * 1024.0))
+        } else {
+            None
+        }
+    }
+
+    /// Run the --on-success or --on-failure command for a finished conversion, if configured
+    async fn run_hook(&self, succeeded: bool, input_path: &str, output_path: &str, size_mb: Option<f64>) {
+        let (name, template) = if succeeded {
+            ("on-success", &self.args.on_success)
+        } else {
+            ("on-failure", &self.args.on_failure)
+        };
+
+        if let Some(template) = template {
+            let command = hooks::expand_command(template, input_path, output_path, size_mb);
+            hooks::run(name, &command).await;
+        }
+    }
+
+    /// Post a completion notification when enabled, printing it instead if it can't be shown
+    async fn notify_finished(&self, succeeded: usize, failed: usize, started: Instant) {
+        if !(self.args.notify || self.config.notify) {
+            return;
+        }
+
+        let title = if failed == 0 { "Conversion finished" } else { "Conversion failed" };
+        let body = format!(
+            "{} succeeded, {} failed in {}",
+            succeeded,
+            failed,
+            notify::format_elapsed(started.elapsed())
+        );
+        if !notify::send(title, &body).await {
+            println!("{}", format!("🔔 {}: {}", title, body).blue());
+        }
+    }
+
+    /// Human-readable size, switching from MB to GB at 1024 MB
+    fn format_size(size_mb: f64) -> String {
+        if size_mb >= 1024.0 {
+            format!("{:.1} GB", size_mb / 1024.0)
+        } else {
+            format!("{:.1} MB", size_mb)
+        }
+    }
+
+    /// Warn when the output ended up larger than its source, which usually means a bad bitrate
+    fn warn_if_larger(&self, input_size_mb: Option<f64>, output_size_mb: Option<f64>) {
+        if let (Some(input_mb), Some(output_mb)) = (input_size_mb, output_size_mb) {
+            if output_mb > input_mb {
+                println!("{}", format!(
+                    "⚠️  Output ({:.2} MB) is larger than the source ({:.2} MB) — check the bitrate settings",
+                    output_mb, input_mb
+                ).yellow());
+            }
+        }
+    }
+
+    /// Ask user for the working directories to scan
+    fn ask_directory(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let default_dirs = &self.config.default_dirs;
+
+        if default_dirs.len() > 1 {
+            println!("{}", format!("📁 Default directories: {}", default_dirs.join(", ")).blue());
+
+            let all_dirs = format!("🗂️  All default directories ({})", default_dirs.len());
+            let other_dir = "📝 Enter a different directory".to_string();
+
+            let mut choices = vec![all_dirs.clone()];
+            choices.extend(default_dirs.iter().cloned());
+            choices.push(other_dir.clone());
+
+            let selection = Select::new("Which directory should be scanned?", choices)
+                .prompt()?;
+
+            return if selection == all_dirs {
+                Ok(default_dirs.clone())
+            } else if selection == other_dir {
+                Ok(vec![self.ask_custom_directory()?])
+            } else {
+                Ok(vec![selection])
+            };
+        }
+
+        println!("{}", format!("📁 Default directory: {}", self.config.default_dir()).blue());
+
+        let use_default = Confirm::new("Use this as the root directory?")
+            .with_default(self.config.default_use_dir)
+            .prompt()?;
+
+        if use_default {
+            Ok(vec![self.config.default_dir().to_string()])
+        } else {
+            Ok(vec![self.ask_custom_directory()?])
+        }
+    }
+
+    /// Prompt until the user enters an existing directory
+    fn ask_custom_directory(&self) -> Result<String, Box<dyn std::error::Error>> {
+        loop {
+            let custom_dir = expand_tilde(Text::new("Enter root directory path:")
+                .prompt()?
+                .trim());
+
+            let path = Path::new(&custom_dir);
+            if path.exists() && path.is_dir() {
+                return Ok(custom_dir);
+            }
+
+            println!("{}", symbols::line(Status::Failure, format!("Directory not found: {}", custom_dir)).red());
+        }
+    }
+
+    /// Resolve a manually entered path against the root directory (URLs and data URIs are kept as-is)
+    fn resolve_manual_input(input: &str, root_dir: &str) -> String {
+        if Self::is_url(input) || data_uri::is_data_uri(input) {
+            input.to_string()
+        } else {
+            Path::new(root_dir).join(expand_tilde(input)).to_string_lossy().to_string()
+        }
+    }
+
+    /// Let user tick one or more video files, or enter one manually
+    ///
+    /// Manual relative paths are resolved against the first root directory. Picking the
+    /// same file twice (as a recent entry and in the list) counts once.
+    fn select_video_files(&self, video_files: &[VideoFile], roots: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let root_dir = &roots[0];
+
+        if video_files.is_empty() {
+            println!("{}", symbols::line(Status::Failure, "No supported video files found in the directory.").red());
+            println!("{}", format!("💡 Supported formats: {}", self.formats.video_extensions().join(", ")).blue());
+
+            let enter_manual = Confirm::new("Would you like to enter a file path or URL manually?")
+                .with_default(true)
+                .prompt()?;
+
+            if !enter_manual {
+                println!("{}", "👋 Goodbye!".blue());
+                tempdir::exit(0);
+            }
+
+            let manual_path = Text::new("Enter video file path or URL:")
+                .prompt()?;
+
+            return Ok(vec![Self::resolve_manual_input(&manual_path, root_dir)]);
+        }
+
+        println!("{}", format!("📹 Found {} video file(s)", video_files.len()).blue());
+
+        // Recently converted files come first as quick picks
+        let recent: Vec<String> = match self.config.recent_files {
+            0 => Vec::new(),
+            limit => RecentFiles::load().entries().iter().take(limit).cloned().collect(),
+        };
+        let mut choices: Vec<String> = recent
+            .iter()
+            .map(|path| {
+                let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy();
+                format!("⭐ Recently: {}", name)
+            })
+            .collect();
+
+        // Tag each file with its root when several directories were scanned
+        choices.extend(video_files.iter().map(|vf| {
+            if roots.len() > 1 {
+                format!("{} — {}", vf, vf.root)
+            } else {
+                vf.to_string()
+            }
+        }));
+        choices.push("📝 Enter file path or URL manually".to_string());
+
+        // In recent-first mode, highlight the newest file even under another sort order
+        let starting_cursor = if self.config.recent_first || self.sort_order() == SortOrder::Recent {
+            video_files
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, vf)| vf.modified)
+                .map_or(0, |(index, _)| recent.len() + index)
+        } else {
+            0
+        };
+
+        let selection = MultiSelect::new("Select video files to convert:", choices)
+            .with_starting_cursor(starting_cursor)
+            .with_help_message("space to tick, enter to convert the ticked files (several are converted as a batch)")
+            .with_validator(|picked: &[ListOption<&String>]| {
+                Ok(match picked.is_empty() {
+                    true => Validation::Invalid("Tick at least one file".into()),
+                    false => Validation::Valid,
+                })
+            })
+            .raw_prompt()?;
+
+        let mut selected: Vec<String> = Vec::new();
+        for option in selection {
+            let path = if let Some(path) = recent.get(option.index) {
+                path.clone()
+            } else if let Some(video_file) = video_files.get(option.index - recent.len()) {
+                video_file.path.clone()
+            } else {
+                let manual_path = Text::new("Enter video file path or URL:")
+                    .prompt()?;
+                Self::resolve_manual_input(&manual_path, root_dir)
+            };
+            if !selected.contains(&path) {
+                selected.push(path);
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Turn several picked paths into a batch, skipping what can only be converted on its own
+    ///
+    /// Files from the scan keep their root; URLs and pasted data can't be batched.
+    fn picked_video_files(video_files: &[VideoFile], selected: &[String]) -> Vec<VideoFile> {
+        selected
+            .iter()
+            .filter_map(|path| {
+                if let Some(video_file) = video_files.iter().find(|vf| &vf.path == path) {
+                    return Some(video_file.clone());
+                }
+                match VideoFile::new(Path::new(path)) {
+                    Ok(video_file) => Some(video_file),
+                    Err(_) => {
+                        println!("{}", format!("⚠️  Skipping {}: only local files can be converted together, pick it on its own", path).yellow());
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Output path for chapter extracts and samples: `<stem> - <label>.<ext>` next to the full output
+    fn chapter_output_path(&self, output_path: &str, label: &str) -> String {
+        let path = Path::new(output_path);
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("mp3");
+        let filename = self.output_filename(&format!("{} - {}", stem, label), extension);
+
+        match path.parent() {
+            Some(parent) => parent.join(filename).to_string_lossy().to_string(),
+            None => filename,
+        }
+    }
+
+    /// Read a timestamps file into one (output path, range) job per segment
+    ///
+    /// Segments must lie within the source; overlapping ones are converted but warned about.
+    async fn segment_jobs(&self, input_path: &str, output_path: &str, segments_file: &str) -> Result<Vec<(String, Vec<TimeRange>)>, ConverterError> {
+        let segments = segments::load(segments_file)?;
+
+        match self.expected_duration(input_path, &[]).await {
+            Some(duration) => {
+                if let Some(segment) = segments.iter().find(|s| s.end > duration + 0.5) {
+                    return Err(ConverterError::InvalidSegmentFile {
+                        path: segments_file.to_string(),
+                        reason: format!(
+                            "'{}' ends at {} but the video is only {} long",
+                            segment.name,
+                            probe::format_timestamp(segment.end),
+                            probe::format_timestamp(duration)
+                        ),
+                    });
+                }
+            }
+            None => println!("{}", "⚠️  Could not determine the video's duration, segment times are unchecked".yellow()),
+        }
+
+        for (a, b) in segments::overlaps(&segments) {
+            println!("{}", format!("⚠️  Segments '{}' and '{}' overlap", a, b).yellow());
+        }
+
+        println!("{}", format!("✂️  Converting {} segment(s) from {}", segments.len(), segments_file).blue());
+
+        let path = Path::new(output_path);
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+        let parent = path.parent().unwrap_or(Path::new(""));
+        Ok(segments
+            .into_iter()
+            .map(|segment| {
+                let filename = self.output_filename(&segment.name, extension);
+                let output = parent.join(filename).to_string_lossy().to_string();
+                (output, vec![TimeRange { start: segment.start, end: segment.end }])
+            })
+            .collect())
+    }
+
+    /// The middle `percent` of the input, e.g. 45%–55% for 10%
+    async fn sample_range(&self, input_path: &str, percent: f64) -> Option<TimeRange> {
+        let duration = self.expected_duration(input_path, &[]).await?;
+        let half = duration * percent / 100.0 / 2.0;
+        Some(TimeRange {
+            start: duration / 2.0 - half,
+            end: duration / 2.0 + half,
+        })
+    }
+
+    /// Let the user pick chapters to extract, returning the (output path, ranges) to convert
+    ///
+    /// Falls back to converting the whole file when the source has no chapters.
+    async fn choose_chapters(&self, input_path: &str, output_path: &str) -> Result<Vec<(String, Vec<TimeRange>)>, Box<dyn std::error::Error>> {
+        let whole_file = vec![(output_path.to_string(), Vec::new())];
+
+        let chapters = match self.probe_cache.probe(input_path, self.headers_for(input_path)).await {
+            Ok(info) => info.chapters,
+            Err(e) => {
+                println!("{}", format!("⚠️  Could not read chapters ({}), converting the whole file", e).yellow());
+                return Ok(whole_file);
+            }
+        };
+
+        if chapters.is_empty() {
+            println!("{}", "ℹ️  No chapters found, converting the whole file".blue());
+            return Ok(whole_file);
+        }
+
+        let labels: Vec<String> = chapters
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                format!("{:02}. {} ({}–{})", i + 1, c.title, probe::format_timestamp(c.start), probe::format_timestamp(c.end))
+            })
+            .collect();
+
+        let seek_help = if self.args.accurate_seek {
+            "Accurate seek: cuts land on the exact sample, but everything before each chapter is decoded (slower)"
+        } else {
+            "Fast seek: cuts snap to the nearest keyframe; use --accurate-seek for sample-exact cuts (slower)"
+        };
+        let selected = MultiSelect::new("Select chapters to extract:", labels)
+            .with_help_message(seek_help)
+            .raw_prompt()?;
+
+        if selected.is_empty() {
+            println!("{}", "ℹ️  No chapters selected, converting the whole file".blue());
+            return Ok(whole_file);
+        }
+
+        let picked: Vec<(usize, &Chapter)> = selected.iter().map(|o| (o.index, &chapters[o.index])).collect();
+        let range = |c: &Chapter| TimeRange { start: c.start, end: c.end };
+
+        let one_per_chapter = "One file per chapter";
+        let per_chapter = picked.len() > 1
+            && Select::new("How should the chapters be saved?", vec!["One file with all selected chapters", one_per_chapter])
+                .prompt()?
+                == one_per_chapter;
+
+        if per_chapter {
+            Ok(picked
+                .iter()
+                .map(|(i, c)| {
+                    let label = format!("{:02} {}", i + 1, c.title);
+                    (self.chapter_output_path(output_path, &label), vec![range(c)])
+                })
+                .collect())
+        } else {
+            let label = picked.iter().map(|(_, c)| c.title.as_str()).collect::<Vec<_>>().join(" + ");
+            let ranges = picked.iter().map(|(_, c)| range(c)).collect();
+            Ok(vec![(self.chapter_output_path(output_path, &label), ranges)])
+        }
+    }
+
+    /// Work out the conversion settings
+    ///
+    /// A preset provides the starting point; otherwise format and bitrate are
+    /// prompted for in interactive mode. Explicit command-line options always win.
+    async fn choose_options(&self, interactive: bool) -> Result<ConversionOptions, Box<dyn std::error::Error>> {
+        let preset = self.args.preset
+            .or(self.args.compat.then_some(Preset::Compat))
+            .or(self.args.ml.then_some(Preset::Ml));
+        // A stream copy has no format or bitrate to ask about
+        let prompt = interactive && preset.is_none() && !self.args.format_from_source;
+
+        let mut options = match preset {
+            Some(preset) => {
+                println!("{}", format!("🎛️  Using preset: {:?}", preset).blue());
+                preset.options()
+            }
+            None => ConversionOptions::default(),
+        };
+
+        match self.requested_format()? {
+            Some(format) => options.set_format(format),
+            None if prompt => options.set_format(Self::ask_format()?),
+            None => {}
+        }
+
+        if self.args.format_from_source {
+            println!("{}", "📦 Copying the source audio as is, without re-encoding".blue());
+            options.copy_audio = true;
+        }
+
+        // With --format-from-source the codec is only known per input; see source_options
+        if let Some(container) = self.args.container {
+            if !options.copy_audio && !container.supports(options.format) {
+                return Err(ConverterError::IncompatibleContainer { format: options.format, container }.into());
+            }
+            options.container = Some(container);
+        }
+
+        match self.args.bitrate {
+            Some(bitrate) => options.bitrate_kbps = bitrate,
+            None if prompt && !options.format.is_lossless() => options.bitrate_kbps = Self::ask_bitrate()?,
+            None => {}
+        }
+
+        if self.args.channels.is_some() {
+            options.channels = self.args.channels;
+        }
+        if self.args.sample_rate.is_some() {
+            options.sample_rate = self.args.sample_rate;
+        }
+        if let Some(resampler) = self.args.resampler {
+            options.resampler = self.check_resampler(resampler).await;
+            options.resampler_precision = self.args.resampler_precision;
+            if options.resampler_precision.is_some() && options.resampler != Resampler::Soxr {
+                println!("{}", "⚠️  --resampler-precision only applies to soxr, ignoring it".yellow());
+            }
+            if options.resampler == Resampler::Soxr && options.sample_rate.is_none() {
+                println!("{}", "ℹ️  The resampler is only used when --sample-rate (or the preset) changes the rate".blue());
+            }
+        }
+        options.segment_seconds = self.args.segment_time.map(|minutes| minutes * 60);
+        options.preview_seconds = self.args.preview;
+        options.record_seconds = self.args.record_duration;
+        options.tags = self.args.tags();
+        options.strip_metadata = self.args.strip_metadata;
+        options.reproducible = self.args.reproducible;
+        options.smart_copy = match (self.args.smart_copy, interactive) {
+            (true, _) => SmartCopy::Auto,
+            (false, true) => SmartCopy::Ask,
+            (false, false) => SmartCopy::Off,
+        };
+        if interactive && options.tags.is_empty() {
+            options.tags = self.ask_tags()?;
+        }
+
+        options.downmix_mono |= self.args.downmix_mono;
+        options.normalize |= self.args.normalize;
+        options.remove_silence |= self.args.remove_silence;
+        options.gapless = self.args.gapless;
+        // Parts have to start and end at the exact sample to line up
+        options.accurate_seek = self.args.accurate_seek || self.args.gapless;
+        options.loop_count = self.args.loop_count;
+        options.threads = self.args.ffmpeg_threads;
+        options.safe_mode = self.args.safe_mode;
+
+        if let Some(hwaccel) = &self.args.hwaccel {
+            options.hwaccel = self.check_hwaccel(hwaccel).await;
+        }
+        options.decryption_key = self.args.key.clone();
+
+        if let Some(output_template) = &self.args.output_template {
+            let checked = if options.copy_audio {
+                template::validate_placeholders(output_template).and_then(|()| match output_template.ends_with(".{ext}") {
+                    true => Ok(()),
+                    false => Err("it must end in .{ext} with --format-from-source, as the extension follows each source's codec".to_string()),
+                })
+            } else {
+                template::validate(output_template, options.format, options.extension())
+            };
+            checked.map_err(ConverterError::InvalidOutputTemplate)?;
+        }
+
+        for &format in self.args.formats.iter().skip(1) {
+            if format != options.format && !options.extra_formats.contains(&format) {
+                options.extra_formats.push(format);
+            }
+        }
+
+        // Nothing gets encoded, so the encoders don't matter
+        if options.copy_audio {
+            return Ok(options);
+        }
+
+        // If ffmpeg itself is missing, the conversion reports that more clearly
+        if let Some(encoders) = ffmpeg::available_encoders().await {
+            self.ensure_encoder(&mut options, &encoders, interactive)?;
+
+            let main_format = options.format;
+            options.extra_formats.retain(|format| {
+                if *format == main_format {
+                    return false;
+                }
+                let available = encoders.contains(format.default_encoder());
+                if !available {
+                    println!("{}", format!(
+                        "⚠️  Your FFmpeg build has no '{}' encoder, skipping {}",
+                        format.default_encoder(), format
+                    ).yellow());
+                }
+                available
+            });
+
+            // Prefer the higher-quality Fraunhofer AAC encoder when ffmpeg was built with it
+            if options.format == AudioFormat::Aac && encoders.contains("libfdk_aac") {
+                options.encoder = "libfdk_aac".to_string();
+            }
+        }
+
+        Ok(options)
+    }
+
+    /// Settings for one input: with `--format-from-source`, where its audio is copied to
+    ///
+    /// The container follows the source codec (or `--container`, if it can hold it). When the
+    /// codec can't be read, the input is encoded to the chosen format instead.
+    async fn source_options(&self, input_path: &str, options: &ConversionOptions) -> ConversionOptions {
+        let mut options = options.clone();
+        if !options.copy_audio {
+            return options;
+        }
+
+        let info = self.probe_cache.probe(input_path, self.headers_for(input_path)).await.ok();
+        let Some(codec) = info.as_ref().and_then(|info| info.audio()).and_then(|audio| audio.codec.clone()) else {
+            println!("{}", format!("⚠️  Couldn't read the source audio codec, encoding to {} instead", options.format).yellow());
+            options.copy_audio = false;
+            return options;
+        };
+
+        let copy = match options.container {
+            Some(container) if container.holds_codec(&codec) => StreamCopy::in_container(&codec, container),
+            Some(container) => {
+                let copy = StreamCopy::for_codec(&codec);
+                println!("{}", format!(
+                    "⚠️  {} audio can't be copied into .{}, using .{} instead",
+                    codec.to_uppercase(), container, copy.extension
+                ).yellow());
+                copy
+            }
+            None => StreamCopy::for_codec(&codec),
+        };
+        options.stream_copy = Some(copy);
+        options
+    }
+
+    /// Format given with --format, or else implied by the --output extension
+    ///
+    /// With `--format-from-source` the extension is whatever the copied codec needs, so it implies nothing.
+    fn requested_format(&self) -> Result<Option<AudioFormat>, ConverterError> {
+        if let Some(format) = self.args.format.or(self.args.codec) {
+            return Ok(Some(format));
+        }
+        if let Some(&first) = self.args.formats.first() {
+            return Ok(Some(first));
+        }
+        if self.args.format_from_source {
+            return Ok(None);
+        }
+
+        let Some(output) = &self.args.output else {
+            return Ok(None);
+        };
+        let extension = Path::new(output)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        match self.formats.output_format(extension) {
+            Some(format) => Ok(Some(format)),
+            None => Err(ConverterError::UnknownOutputFormat { extension: extension.to_string() }),
+        }
+    }
+
+    /// Ask which output format to use
+    fn ask_format() -> Result<AudioFormat, InquireError> {
+        Select::new("Select output format:", AudioFormat::ALL.to_vec()).prompt()
+    }
+
+    /// Ask which bitrate to use, starting at the default
+    fn ask_bitrate() -> Result<u32, InquireError> {
+        let labels: Vec<String> = BITRATES.iter().map(|b| format!("{} kbps", b)).collect();
+        let default_index = BITRATES.iter().position(|&b| b == DEFAULT_BITRATE).unwrap_or(0);
+        let selection = Select::new("Select bitrate:", labels)
+            .with_starting_cursor(default_index)
+            .raw_prompt()?;
+        Ok(BITRATES[selection.index])
+    }
+
+    /// Offer to retry (as is, in safe mode or with another format), skip or quit after a failure
+    ///
+    /// Without a terminal to ask at, this always quits.
+    async fn ask_after_failure(&self, options: &mut ConversionOptions, output_path: &mut String) -> Result<AfterFailure, Box<dyn std::error::Error>> {
+        if !std::io::stdin().is_terminal() {
+            return Ok(AfterFailure::Quit);
+        }
+
+        let retry = "🔁 Retry";
+        let retry_safe = "🛟 Retry in safe mode (slower, tolerates damaged files)";
+        let change_format = "🎚️  Change format or bitrate and retry";
+        let skip = "⏭️  Skip and continue";
+        let quit = "🚪 Quit";
+
+        let mut choices = vec![retry];
+        if !options.safe_mode {
+            choices.push(retry_safe);
+        }
+        choices.extend([change_format, skip, quit]);
+
+        // Escape or Ctrl+C at the menu counts as quitting
+        let choice = Select::new("What would you like to do?", choices).prompt().unwrap_or(quit);
+        if choice == retry_safe {
+            options.safe_mode = true;
+        } else if choice == change_format {
+            options.set_format(Self::ask_format()?);
+            if !options.format.is_lossless() {
+                options.bitrate_kbps = Self::ask_bitrate()?;
+            }
+            let format = options.format;
+            options.extra_formats.retain(|&extra| extra != format);
+            if let Some(encoders) = ffmpeg::available_encoders().await {
+                self.ensure_encoder(options, &encoders, true)?;
+            }
+            *output_path = Path::new(output_path.as_str())
+                .with_extension(options.extension())
+                .to_string_lossy()
+                .to_string();
+        } else if choice == skip {
+            return Ok(AfterFailure::Skip);
+        } else if choice == quit {
+            return Ok(AfterFailure::Quit);
+        }
+        Ok(AfterFailure::Retry)
+    }
+
+    /// Optionally ask for title/artist/album/year/genre tags
+    fn ask_tags(&self) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+        let add_tags = Confirm::new("Add title/artist tags to the output?")
+            .with_default(false)
+            .prompt()?;
+
+        if !add_tags {
+            return Ok(Vec::new());
+        }
+
+        println!("{}", "💡 Leave a field empty to skip it".blue());
+
+        let mut tags = Vec::new();
+        for (key, label) in [("title", "Title:"), ("artist", "Artist:"), ("album", "Album:")] {
+            let value = Text::new(label).prompt()?;
+            if !value.trim().is_empty() {
+                tags.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+
+        loop {
+            let year = Text::new("Year:").prompt()?;
+            let year = year.trim();
+            if year.is_empty() {
+                break;
+            }
+            if year.len() <= 4 && year.chars().all(|c| c.is_ascii_digit()) {
+                tags.push(("date".to_string(), year.to_string()));
+                break;
+            }
+            println!("{}", symbols::line(Status::Failure, "Year must be a number, e.g. 2024").red());
+        }
+
+        let genre = Text::new("Genre:").prompt()?;
+        if !genre.trim().is_empty() {
+            tags.push(("genre".to_string(), genre.trim().to_string()));
+        }
+
+        Ok(tags)
+    }
+
+    /// Validate a requested hardware decoder, warning and skipping it when ffmpeg doesn't support it
+    async fn check_hwaccel(&self, requested: &str) -> Option<String> {
+        if requested == "auto" {
+            return Some(requested.to_string());
+        }
+
+        match ffmpeg::available_hwaccels().await {
+            Some(available) if available.iter().any(|h| h == requested) => Some(requested.to_string()),
+            Some(available) => {
+                let supported = if available.is_empty() { "none".to_string() } else { available.join(", ") };
+                println!("{}", format!(
+                    "⚠️  Hardware acceleration '{}' isn't supported by your FFmpeg (available: {}), decoding in software",
+                    requested, supported
+                ).yellow());
+                None
+            }
+            // ffmpeg couldn't be run; the conversion will report that
+            None => None,
+        }
+    }
+
+    /// Use soxr only if ffmpeg was built with it, falling back to swr with a warning
+    async fn check_resampler(&self, requested: Resampler) -> Resampler {
+        if requested != Resampler::Soxr {
+            return requested;
+        }
+        match ffmpeg::has_soxr().await {
+            Some(false) => {
+                println!("{}", "⚠️  Your FFmpeg build has no soxr resampler (it needs --enable-libsoxr), using swr".yellow());
+                Resampler::Swr
+            }
+            // ffmpeg couldn't be run; the conversion will report that
+            _ => requested,
+        }
+    }
+
+    /// Switch to a fallback format when ffmpeg lacks the chosen encoder
+    ///
+    /// Interactive runs are asked which available format to use; otherwise the
+    /// `--fallback-format` option must say, or the run fails.
+    fn ensure_encoder(&self, options: &mut ConversionOptions, encoders: &HashSet<String>, interactive: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if encoders.contains(&options.encoder) {
+            return Ok(());
+        }
+
+        let missing = options.encoder.clone();
+        println!("{}", format!("⚠️  Your FFmpeg build has no '{}' encoder for {}", missing, options.format).yellow());
+
+        let fallback = match self.args.fallback_format {
+            Some(format) => format,
+            None if interactive => {
+                let available: Vec<AudioFormat> = AudioFormat::ALL
+                    .into_iter()
+                    .filter(|f| encoders.contains(f.default_encoder()))
+                    .collect();
+                if available.is_empty() {
+                    return Err(ConverterError::EncoderMissing { encoder: missing }.into());
+                }
+                Select::new("Convert to which format instead?", available)
+                    .prompt()?
+            }
+            None => return Err(ConverterError::EncoderMissing { encoder: missing }.into()),
+        };
+
+        if !encoders.contains(fallback.default_encoder()) {
+            return Err(ConverterError::EncoderMissing { encoder: fallback.default_encoder().to_string() }.into());
+        }
+
+        println!("{}", format!("↪️  Falling back to {}", fallback).blue());
+        options.set_format(fallback);
+        Ok(())
+    }
+
+    /// Print what a batch run would do with each file, without converting anything (`--plan`)
+    async fn print_plan(&self, video_files: &[VideoFile], roots: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if video_files.is_empty() {
+            println!("{}", symbols::line(Status::Failure, "No supported video files found in the directory.").red());
+            return Ok(());
+        }
+
+        let options = self.choose_options(false).await?;
+        let (mut new, mut changed, mut up_to_date, mut empty) = (0, 0, 0, 0);
+
+        println!("{}", format!("📋 Plan for {} file(s), nothing is converted:", video_files.len()).blue());
+        for (index, video_file) in video_files.iter().enumerate() {
+            let options = self.source_options(&video_file.path, &options).await;
+            let output_path = self.get_output_path(&video_file.path, roots, &options, index + 1);
+            let line = format!("{} → {}", video_file.name, output_path);
+
+            if video_file.is_empty() {
+                empty += 1;
+                println!("{}", format!("   ! empty       {} (skipped, 0 bytes)", video_file.name).red());
+                continue;
+            }
+            match self.output_state(video_file, &output_path, &options) {
+                OutputState::New => {
+                    new += 1;
+                    println!("{}", format!("   + new         {}", line).green());
+                }
+                OutputState::Changed => {
+                    changed += 1;
+                    println!("{}", format!("   ~ changed     {}", line).yellow());
+                }
+                OutputState::UpToDate if self.args.incremental => {
+                    up_to_date += 1;
+                    println!("{}", format!("   = up to date  {} (skipped)", line).dimmed());
+                }
+                OutputState::UpToDate => {
+                    up_to_date += 1;
+                    println!("{}", format!("   = up to date  {} (converted again without --incremental)", line).dimmed());
+                }
+            }
+        }
+
+        let (again, skipped_up_to_date) = if self.args.incremental { (0, up_to_date) } else { (up_to_date, 0) };
+        println!();
+        println!("{}", format!(
+            "📊 Plan: {} to convert ({} new, {} changed, {} again), {} skipped ({} up to date, {} empty)",
+            new + changed + again, new, changed, again, skipped_up_to_date + empty, skipped_up_to_date, empty
+        ).yellow());
+        Ok(())
+    }
+
+    /// Write a `--oneline` result line, if that mode is on
+    fn record_oneline(&self, input_path: &str, output_path: &str, status: &str, size_mb: Option<f64>, started: Instant) {
+        if let Some(oneline) = &self.oneline {
+            oneline.record(input_path, output_path, status, size_mb, started.elapsed().as_secs_f64());
         }
     }
 
-    /// Check if a string is a valid URL
-    fn is_url(input: &str) -> bool {
-        if let Ok(url) = Url::parse(input) {
-            matches!(url.scheme(), "http" | "https")
-        } else {
-            false
+    /// Project how long converting all the files would take from a short calibration encode
+    ///
+    /// Files ffprobe can't time are estimated from their size, at the average
+    /// seconds per MB of the others.
+    async fn estimate_batch(&self, video_files: &[VideoFile]) -> Result<(), Box<dyn std::error::Error>> {
+        if video_files.is_empty() {
+            println!("{}", symbols::line(Status::Failure, "No supported video files found in the directory.").red());
+            return Ok(());
+        }
+
+        let options = self.choose_options(false).await?;
+
+        let pb = Self::spinner();
+        pb.set_message(format!("🔍 Reading the length of {} file(s)...", video_files.len()));
+        let mut durations = Vec::with_capacity(video_files.len());
+        for video_file in video_files {
+            let duration = self.expected_duration(&video_file.path, &[]).await;
+            durations.push((video_file, duration));
+        }
+
+        let timed: Vec<(&VideoFile, f64)> = durations.iter().filter_map(|&(f, d)| Some((f, d?))).collect();
+        let Some(&(sample, sample_duration)) = timed.iter().max_by(|a, b| a.1.total_cmp(&b.1)) else {
+            pb.finish_and_clear();
+            println!("{}", symbols::line(Status::Failure, "Could not determine the length of any file, so there is nothing to estimate from").red());
+            return Ok(());
+        };
+
+        pb.set_message(format!("⏱️  Calibrating encode speed on {}...", sample.name));
+        let speed = estimate::measure_speed(&sample.path, sample_duration, &options).await;
+        pb.finish_and_clear();
+        let Some(speed) = speed else {
+            println!("{}", symbols::line(Status::Failure, format!("Calibration encode of {} failed", sample.name)).red());
+            return Ok(());
+        };
+
+        let timed_seconds: f64 = timed.iter().map(|(_, d)| d).sum();
+        let timed_mb: f64 = timed.iter().map(|(f, _)| f.size_mb).sum();
+        let untimed_mb: f64 = durations.iter().filter(|(_, d)| d.is_none()).map(|(f, _)| f.size_mb).sum();
+        let guessed_seconds = if timed_mb > 0.0 { untimed_mb * timed_seconds / timed_mb } else { 0.0 };
+        let total_seconds = timed_seconds + guessed_seconds;
+
+        println!("{}", format!("⚡ Encode speed: {:.1}x realtime ({} at {} kbps)", speed, options.format, options.bitrate_kbps).blue());
+        println!("{}", format!(
+            "📹 {} file(s), about {} of video",
+            video_files.len(),
+            probe::format_timestamp(total_seconds)
+        ).blue());
+        if timed.len() < video_files.len() {
+            println!("{}", format!(
+                "ℹ️  {} file(s) had no readable length and were estimated from their size",
+                video_files.len() - timed.len()
+            ).blue());
         }
+        println!("{}", format!("⏱️  Estimated total time: {}", estimate::format_estimate(total_seconds / speed)).green());
+        Ok(())
     }
 
-    /// Get all video files in a directory
-    fn get_video_files(&self, directory: &str) -> Result<Vec<VideoFile>, Box<dyn std::error::Error>> {
-        let mut video_files = Vec::new();
-        let dir_path = Path::new(directory);
+    /// Accept conversion jobs over a Unix socket until a front-end asks to shut down
+    ///
+    /// Each connection sends one JSON request per line and gets JSON events back.
+    /// Jobs run one at a time, using the command-line settings as defaults.
+    #[cfg(unix)]
+    async fn serve(&self, socket_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let defaults = self.choose_options(false).await?;
+        let (listener, _socket) = serve::SocketGuard::bind(socket_path)?;
+        println!("{}", format!("🔌 Serving conversion jobs on {}", socket_path).blue());
 
-        if !dir_path.exists() || !dir_path.is_dir() {
-            return Ok(video_files);
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => accepted?.0,
+                _ = tokio::signal::ctrl_c() => break,
+            };
+
+            println!("{}", "🔗 Front-end connected".blue());
+            if self.serve_connection(stream, &defaults).await {
+                break;
+            }
+            println!("{}", "🔗 Front-end disconnected".blue());
         }
 
-        let entries = fs::read_dir(dir_path)?;
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+        println!("{}", "👋 Server stopped".blue());
+        Ok(())
+    }
+
+    /// Handle one front-end connection, returning true when it asked the server to stop
+    ///
+    /// Requests keep being read while a job runs, so it can be cancelled and later jobs
+    /// queue up behind it.
+    #[cfg(unix)]
+    async fn serve_connection(&self, stream: tokio::net::UnixStream, defaults: &ConversionOptions) -> bool {
+        let (reader, mut writer) = stream.into_split();
 
-            if path.is_file() {
-                if let Ok(video_file) = VideoFile::new(&path) {
-                    if video_file.is_supported_video() {
-                        video_files.push(video_file);
+        // Progress arrives from ffmpeg's reader task, so all events go through one writer
+        let (events, mut outgoing) = mpsc::unbounded_channel::<Event>();
+        let writer_task = tokio::spawn(async move {
+            while let Some(event) = outgoing.recv().await {
+                if writer.write_all(event.to_line().as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let _ = events.send(Event::Ready { version: env!("CARGO_PKG_VERSION") });
+
+        let mut lines = BufReader::new(reader).lines();
+        let mut queued = VecDeque::new();
+        let mut reading = true;
+        let mut shutdown = false;
+        while !shutdown {
+            let job = match queued.pop_front() {
+                Some(job) => job,
+                None if !reading => break,
+                None => match Self::next_request(&mut lines, &events).await {
+                    Some(Request::Job(job)) => job,
+                    Some(Request::Command { command: serve::Command::Cancel }) => {
+                        let _ = events.send(Event::Error { message: "No job is running".to_string() });
+                        continue;
                     }
+                    Some(Request::Command { command: serve::Command::Shutdown }) => {
+                        shutdown = true;
+                        break;
+                    }
+                    None => break,
+                },
+            };
+
+            let cancel = self.cancel.child_token();
+            let running = self.serve_job(job, defaults, &events, &cancel);
+            tokio::pin!(running);
+            loop {
+                tokio::select! {
+                    _ = &mut running => break,
+                    request = Self::next_request(&mut lines, &events), if reading && !shutdown => match request {
+                        Some(Request::Job(job)) => queued.push_back(job),
+                        Some(Request::Command { command: serve::Command::Cancel }) => cancel.cancel(),
+                        Some(Request::Command { command: serve::Command::Shutdown }) => {
+                            shutdown = true;
+                            cancel.cancel();
+                        }
+                        None => reading = false,
+                    },
                 }
             }
         }
 
-        // Sort by filename (case-insensitive)
-        video_files.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        if shutdown {
+            let _ = events.send(Event::Shutdown);
+        }
+        drop(events);
+        let _ = writer_task.await;
+        shutdown
+    }
 
-        Ok(video_files)
+    /// Read the front-end's next request, answering lines that don't parse with an error event
+    ///
+    /// Returns `None` when the connection is closed; Ctrl+C reads as a shutdown request.
+    #[cfg(unix)]
+    async fn next_request(
+        lines: &mut tokio::io::Lines<BufReader<tokio::net::unix::OwnedReadHalf>>,
+        events: &mpsc::UnboundedSender<Event>,
+    ) -> Option<Request> {
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => line,
+                _ = tokio::signal::ctrl_c() => return Some(Request::Command { command: serve::Command::Shutdown }),
+            };
+            let Ok(Some(line)) = line else { return None };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<Request>(&line) {
+                Ok(request) => return Some(request),
+                Err(e) => {
+                    let _ = events.send(Event::Error { message: format!("Invalid request: {}", e) });
+                }
+            }
+        }
     }
 
-    /// Generate output MP3 path based on input path
-    fn get_output_path(&self, input_path: &str) -> String {
-        if Self::is_url(input_path) {
-            if let Ok(url) = Url::parse(input_path) {
-                let path = url.path();
-                let filename = Path::new(path)
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("output");
-                return format!("{}.mp3", filename);
+    /// Run one job received by the server, reporting progress and the result as events
+    ///
+    /// `cancel` stops just this job; the server carries on with the next one.
+    #[cfg(unix)]
+    async fn serve_job(&self, job: serve::Job, defaults: &ConversionOptions, events: &mpsc::UnboundedSender<Event>, cancel: &CancelToken) {
+        let mut options = defaults.clone();
+        if let Some(name) = &job.format {
+            match self.formats.output_format(name) {
+                Some(format) => options.set_format(format),
+                None => {
+                    let _ = events.send(Event::Failed { input: job.input, error: format!("Unknown format '{}'", name) });
+                    return;
+                }
             }
-            return "output.mp3".to_string();
         }
+        if let Some(bitrate) = job.bitrate {
+            options.bitrate_kbps = bitrate;
+        }
+        let options = self.source_options(&job.input, &options).await;
 
-        let path = Path::new(input_path);
-        let filename = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output");
+        let output = job
+            .output
+            .clone()
+            .unwrap_or_else(|| self.get_output_path(&job.input, &[], &options, 1));
+        let _ = events.send(Event::Started { input: job.input.clone(), output: output.clone() });
 
-        if let Some(parent) = path.parent() {
-            parent.join(format!("{}.mp3", filename)).to_string_lossy().to_string()
-        } else {
-            format!("{}.mp3", filename)
-        }
+        let progress_events = events.clone();
+        let on_progress: ProgressCallback = Arc::new(move |tracker: &ProgressTracker| {
+            let _ = progress_events.send(Event::Progress { percent: tracker.percent(), speed: tracker.speed() });
+        });
+
+        *self.job_cancel.lock().unwrap_or_else(|e| e.into_inner()) = Some(cancel.clone());
+        let result = self.convert_to_mp3(&job.input, &output, &[], &options, Some(on_progress)).await;
+        *self.job_cancel.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+        let event = match result {
+            Ok(Conversion::Succeeded) => Event::Finished {
+                size_mb: self.output_size_mb(&output, &options),
+                fingerprint: if self.args.fingerprint { fingerprint::compute(&output).await } else { None },
+                input: job.input,
+                output,
+            },
+            Ok(Conversion::Failed) => Event::Failed { input: job.input, error: "FFmpeg failed, see the server output".to_string() },
+            Ok(Conversion::Skipped) => Event::Skipped { input: job.input },
+            Err(ConverterError::Cancelled) => Event::Cancelled { input: job.input },
+            Err(e) => Event::Failed { input: job.input, error: e.to_string() },
+        };
+        let _ = events.send(event);
     }
 
-    /// Convert video to MP3 using ffmpeg
-    async fn convert_to_mp3(&self, input_path: &str, output_path: &str) -> Result<bool, Box<dyn std::error::Error>> {
-        println!("{}", format!("🔄 Converting: {} → {}", 
-            Path::new(input_path).file_name().unwrap_or_default().to_string_lossy(),
-            Path::new(output_path).file_name().unwrap_or_default().to_string_lossy()
-        ).blue());
+    /// Convert every video file in the list, continuing past individual failures
+    async fn run_batch(&self, video_files: &[VideoFile], roots: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if video_files.is_empty() {
+            println!("{}", symbols::line(Status::Failure, "No supported video files found in the directory.").red());
+            return Ok(());
+        }
 
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.green} {msg}")
-                .unwrap()
-        );
-        pb.set_message("🎬 FFmpeg started...");
+        println!("{}", format!("📹 Converting {} video file(s)", video_files.len()).blue());
 
-        let mut cmd = Command::new("ffmpeg")
-            .args(&[
-                "-i", input_path,
-                "-vn",
-                "-acodec", "libmp3lame",
-                "-ab", "192k",
-                "-y",
-                output_path,
-            ])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+        let options = self.choose_options(false).await?;
 
-        // Read stderr for progress information
-        if let Some(stderr) = cmd.stderr.take() {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
+        let started = Instant::now();
+        let mut succeeded = 0;
+        let mut failed = 0;
+        let mut skipped = 0;
+        // Sizes of the successfully converted sources and what they turned into
+        let mut source_mb = 0.0;
+        let mut output_mb = 0.0;
+        // Everything written, for --archive
+        let mut written = Vec::new();
+        // 0-byte inputs, listed again at the end so they can be re-downloaded
+        let mut empty = Vec::new();
+        // One summary-table row per file
+        let mut rows = Vec::new();
 
-            tokio::spawn(async move {
-                while let Ok(Some(_line)) = lines.next_line().await {
-                    // Could parse FFmpeg progress here if needed
+        for (index, video_file) in video_files.iter().enumerate() {
+            let options = self.source_options(&video_file.path, &options).await;
+            let output_path = self.get_output_path(&video_file.path, roots, &options, index + 1);
+
+            let file_started = Instant::now();
+
+            println!();
+            println!("{}", format!("[{}/{}] {}", index + 1, video_files.len(), video_file.name).cyan());
+
+            if video_file.is_empty() {
+                println!("{}", symbols::line(Status::Skip, "Skipped: input file is empty (0 bytes)").yellow());
+                empty.push(video_file.path.clone());
+                skipped += 1;
+                self.record_oneline(&video_file.path, &output_path, "skipped", None, file_started);
+                rows.push(Self::batch_row(video_file, "skipped (empty)", None, None, file_started));
+                continue;
+            }
+            if self.args.incremental && self.output_state(video_file, &output_path, &options) == OutputState::UpToDate {
+                println!("{}", symbols::line(Status::Skip, "Skipped: up to date (the output is newer than the source)").yellow());
+                skipped += 1;
+                self.record_oneline(&video_file.path, &output_path, "skipped", None, file_started);
+                rows.push(Self::batch_row(video_file, "up to date", None, None, file_started));
+                continue;
+            }
+
+            let (status, size_mb) = match self.convert_to_mp3(&video_file.path, &output_path, &[], &options, None).await {
+                Ok(Conversion::Succeeded) => {
+                    // The summary table covers this unless every detail is wanted
+                    if self.args.verbose {
+                        match options.segment_seconds {
+                            Some(_) => {
+                                let segments = self.produced_files(&output_path, &options);
+                                println!("{}", format!("💾 Saved {} segment(s) next to: {}", segments.len(), output_path).green());
+                            }
+                            None => println!("{}", format!("💾 Saved: {}", output_path).green()),
+                        }
+                        self.report_extra_outputs(&output_path, &options);
+                    }
+                    let output_size_mb = self.output_size_mb(&output_path, &options);
+                    self.warn_if_larger(Some(video_file.size_mb), output_size_mb);
+                    source_mb += video_file.size_mb;
+                    output_mb += output_size_mb.unwrap_or(0.0);
+                    written.extend(self.all_outputs(&output_path, &options));
+                    succeeded += 1;
+                    self.run_hook(true, &video_file.path, &output_path, output_size_mb).await;
+                    ("succeeded", output_size_mb)
                 }
-            });
+                Ok(Conversion::Failed) => {
+                    failed += 1;
+                    self.run_hook(false, &video_file.path, &output_path, None).await;
+                    ("failed", None)
+                }
+                Ok(Conversion::Skipped) => {
+                    skipped += 1;
+                    ("skipped", None)
+                }
+                Err(ConverterError::FfmpegNotFound) => {
+                    println!("{}", symbols::line(Status::Failure, ConverterError::FfmpegNotFound).red());
+                    self.record_oneline(&video_file.path, &output_path, "failed", None, file_started);
+                    tempdir::exit(1);
+                }
+                Err(ConverterError::Cancelled) => {
+                    self.record_oneline(&video_file.path, &output_path, "cancelled", None, file_started);
+                    return Err(ConverterError::Cancelled.into());
+                }
+                Err(e) => {
+                    println!("{}", symbols::line(Status::Failure, e).red());
+                    failed += 1;
+                    self.run_hook(false, &video_file.path, &output_path, None).await;
+                    ("failed", None)
+                }
+            };
+            self.record_oneline(&video_file.path, &output_path, status, size_mb, file_started);
+
+            // Already probed when the output was reported, so this is a cache hit
+            let duration = match self.produced_files(&output_path, &options).first() {
+                Some(produced) if status == "succeeded" => self.probe_cache.probe(produced, &[]).await.ok().and_then(|info| info.duration),
+                _ => None,
+            };
+            rows.push(Self::batch_row(video_file, status, size_mb, duration, file_started));
         }
 
-        pb.set_message("Converting... Please wait");
-        
-        let status = cmd.wait().await?;
-        pb.finish_and_clear();
+        println!();
+        table::print(&BATCH_COLUMNS, &rows);
 
-        if status.success() {
-            println!("{}", "✅ Conversion completed successfully!".green());
-            Ok(true)
-        } else {
-            match status.code() {
-                Some(code) => println!("{}", format!("❌ FFmpeg failed with exit code: {}", code).red()),
-                None => println!("{}", "❌ FFmpeg process was terminated".red()),
+        println!();
+        println!("{}", format!(
+            "📊 Batch finished: {} succeeded, {} failed, {} skipped",
+            succeeded, failed, skipped
+        ).yellow());
+        if !empty.is_empty() {
+            println!("{}", format!("⚠️  {} empty (0-byte) input(s), probably interrupted downloads; try downloading them again:", empty.len()).yellow());
+            for path in &empty {
+                println!("{}", format!("   • {}", path).yellow());
             }
-            Ok(false)
         }
-    }
+        if succeeded > 0 {
+            let change = if output_mb <= source_mb {
+                format!("saved {}", Self::format_size(source_mb - output_mb))
+            } else {
+                format!("grew by {}", Self::format_size(output_mb - source_mb))
+            };
+            println!("{}", format!(
+                "💽 Source: {} → Output: {} ({})",
+                Self::format_size(source_mb), Self::format_size(output_mb), change
+            ).yellow());
+        }
 
-    /// Get file size in MB
-    fn get_file_size_mb(&self, file_path: &str) -> Option<f64> {
-        if let Ok(metadata) = fs::metadata(file_path) {
-            Some(metadata.len() as f64 / (1024.0 * 1024.0))
-        } else {
-            None
+        if let Some(archive_path) = &self.args.archive {
+            self.archive_outputs(archive_path, &written).await;
         }
+
+        self.notify_finished(succeeded, failed, started).await;
+
+        if failed > 0 {
+            tempdir::exit(1);
+        }
+
+        Ok(())
     }
 
-    /// Ask user for working directory
-    fn ask_directory(&self) -> Result<String, Box<dyn std::error::Error>> {
-        println!("{}", format!("📁 Default directory: {}", self.config.default_dir).blue());
+    /// Convert everything dropped into the queue folder, moving each source to `done/` or `failed/`
+    ///
+    /// The folder is scanned again after every pass so files added in the meantime are picked
+    /// up, and the run ends once a scan finds nothing left to try. Files modified within the
+    /// last few seconds are left for a later pass, as they may still be being copied in.
+    /// Without `--output-dir`, outputs go into `done/` next to their source.
+    async fn run_queue(&self, queue_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let queue = Path::new(queue_dir);
+        if !queue.is_dir() {
+            return Err(format!("Queue folder not found: {}", queue_dir).into());
+        }
+        let done_dir = queue.join("done");
+        let failed_dir = queue.join("failed");
+        fs::create_dir_all(&done_dir)?;
+        fs::create_dir_all(&failed_dir)?;
 
-        let use_default = Confirm::new("Use this as the root directory?")
-            .with_default(true)
-            .prompt()?;
+        println!("{}", format!("📥 Processing the queue in {}", queue_dir).blue());
+        let options = self.choose_options(false).await?;
+        let roots = [queue_dir.to_string()];
 
-        if use_default {
-            Ok(self.config.default_dir.clone())
-        } else {
-            loop {
-                let custom_dir = Text::new("Enter root directory path:")
-                    .prompt()?;
+        let started = Instant::now();
+        let (mut succeeded, mut failed) = (0, 0);
+        // Files left in place (skipped, or taken by another process) aren't tried again
+        let mut attempted = HashSet::new();
+        loop {
+            let (video_files, _) = self.filter_video_files(self.get_video_files(&roots)?);
+            let pending: Vec<VideoFile> = video_files.into_iter().filter(|vf| !attempted.contains(&vf.path)).collect();
+            if pending.is_empty() {
+                break;
+            }
+
+            let (ready, settling): (Vec<VideoFile>, Vec<VideoFile>) = pending.into_iter().partition(Self::is_settled);
+            if ready.is_empty() {
+                println!("{}", format!("⏳ Waiting for {} file(s) still being written", settling.len()).blue());
+                tokio::time::sleep(QUEUE_SETTLE_TIME).await;
+                continue;
+            }
+
+            for video_file in ready {
+                attempted.insert(video_file.path.clone());
+                println!();
+                println!("{}", format!("[{}] {}", succeeded + failed + 1, video_file.name).cyan());
 
-                let path = Path::new(&custom_dir);
-                if path.exists() && path.is_dir() {
-                    return Ok(custom_dir);
+                let options = self.source_options(&video_file.path, &options).await;
+                let mut output_path = self.get_output_path(&video_file.path, &roots, &options, succeeded + failed + 1);
+                if self.args.output_dir.is_none() {
+                    let file_name = Path::new(&output_path).file_name().unwrap_or_default().to_owned();
+                    output_path = done_dir.join(file_name).to_string_lossy().to_string();
                 }
 
-                println!("{}", format!("❌ Directory not found: {}", custom_dir).red());
+                let target_dir = match self.convert_to_mp3(&video_file.path, &output_path, &[], &options, None).await {
+                    Ok(Conversion::Succeeded) => {
+                        succeeded += 1;
+                        self.run_hook(true, &video_file.path, &output_path, self.output_size_mb(&output_path, &options)).await;
+                        &done_dir
+                    }
+                    Ok(Conversion::Skipped) => {
+                        println!("{}", symbols::line(Status::Skip, "Left in the queue").yellow());
+                        continue;
+                    }
+                    Ok(Conversion::Failed) => {
+                        failed += 1;
+                        self.run_hook(false, &video_file.path, &output_path, None).await;
+                        &failed_dir
+                    }
+                    Err(e @ (ConverterError::FfmpegNotFound | ConverterError::Cancelled)) => return Err(e.into()),
+                    Err(e) => {
+                        println!("{}", symbols::line(Status::Failure, e).red());
+                        failed += 1;
+                        self.run_hook(false, &video_file.path, &output_path, None).await;
+                        &failed_dir
+                    }
+                };
+
+                let source = Path::new(&video_file.path);
+                let destination = Self::free_path(target_dir, source.file_name().unwrap_or_default());
+                match fs::rename(source, &destination) {
+                    Ok(()) => println!("{}", format!("📦 Moved to {}", destination.display()).blue()),
+                    Err(e) => println!("{}", format!("⚠️  Could not move {} to {}: {}", video_file.name, target_dir.display(), e).yellow()),
+                }
             }
         }
+
+        println!();
+        println!("{}", format!("📭 Queue empty: {} succeeded, {} failed", succeeded, failed).yellow());
+        self.notify_finished(succeeded, failed, started).await;
+
+        if failed > 0 {
+            tempdir::exit(1);
+        }
+        Ok(())
     }
 
-    /// Let user select a video file or enter manual input
-    fn select_video_file(&self, video_files: &[VideoFile], _root_dir: &str) -> Result<String, Box<dyn std::error::Error>> {
-        if video_files.is_empty() {
-            println!("{}", "❌ No supported video files found in the directory.".red());
-            println!("{}", "💡 Supported formats: mp4, avi, mov, mkv, flv, wmv, webm, m4v, 3gp".blue());
+    /// Whether a queued file has gone unmodified long enough to be complete
+    fn is_settled(video_file: &VideoFile) -> bool {
+        match video_file.modified.and_then(|modified| modified.elapsed().ok()) {
+            Some(age) => age >= QUEUE_SETTLE_TIME,
+            // Without a usable timestamp there's nothing to wait for
+            None => true,
+        }
+    }
 
-            let enter_manual = Confirm::new("Would you like to enter a file path or URL manually?")
-                .with_default(true)
-                .prompt()?;
+    /// `dir/name`, or `dir/name (2)`, `dir/name (3)`... if that's taken
+    fn free_path(dir: &Path, name: &std::ffi::OsStr) -> PathBuf {
+        let candidate = dir.join(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        let name = Path::new(name);
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = name.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+        (2..)
+            .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+            .find(|path| !path.exists())
+            .unwrap_or(candidate)
+    }
 
-            if !enter_manual {
-                println!("{}", "👋 Goodbye!".blue());
-                std::process::exit(0);
+    /// Summary-table row for one batch file, with the status colored by outcome
+    fn batch_row(video_file: &VideoFile, status: &str, output_mb: Option<f64>, duration: Option<f64>, started: Instant) -> Vec<Cell> {
+        let color = match status {
+            "succeeded" => Color::DarkGreen,
+            "failed" => Color::DarkRed,
+            _ => Color::DarkYellow,
+        };
+        vec![
+            Cell::plain(&video_file.name),
+            Cell::colored(status, color),
+            Cell::plain(Self::format_size(video_file.size_mb)),
+            Cell::plain(output_mb.map_or_else(|| "—".to_string(), Self::format_size)),
+            Cell::plain(duration.map_or_else(|| "—".to_string(), probe::format_timestamp)),
+            Cell::plain(format!("{:.1}s", started.elapsed().as_secs_f64())),
+        ]
+    }
+
+    /// Encode a clip from the middle of the input at each `--compare` bitrate and compare them
+    ///
+    /// The samples go to temp files, are listed side by side with their size and
+    /// `volumedetect` levels, can be played with the configured `PLAYER`, and are
+    /// removed at the end. Nothing is written next to the input.
+    async fn compare_bitrates(&self, input_path: &str, bitrates: &[u32]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut options = self.choose_options(false).await?;
+        if options.format.is_lossless() {
+            return Err(format!("--compare compares bitrates, which {} doesn't use; pick a lossy --format", options.format).into());
+        }
+        options.extra_formats.clear();
+
+        let (start, end) = compare::clip_bounds(self.expected_duration(input_path, &[]).await);
+        let range = TimeRange { start, end };
+        println!("{}", format!(
+            "🆚 Comparing {} at {} on {}–{}",
+            options.format,
+            bitrates.iter().map(|kbps| format!("{} kbps", kbps)).collect::<Vec<_>>().join(" and "),
+            probe::format_timestamp(start),
+            probe::format_timestamp(end)
+        ).blue());
+
+        let mut samples = Vec::new();
+        for (&bitrate_kbps, label) in bitrates.iter().zip('A'..) {
+            options.bitrate_kbps = bitrate_kbps;
+            let path = tempdir::path(&format!("compare-{}-{}k.{}", label, bitrate_kbps, options.extension()))?;
+            let path_str = path.to_string_lossy().to_string();
+
+            let pb = Self::spinner();
+            pb.set_message(format!("🎧 Encoding sample {} at {} kbps...", label, bitrate_kbps));
+            let status = Command::new("ffmpeg")
+                .args(["-nostdin", "-hide_banner", "-v", "error"])
+                .args(self.ffmpeg_args(input_path, &path_str, &[range], &options))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await;
+            drop(pb);
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(_) => return Err(format!("Encoding the {} kbps sample failed", bitrate_kbps).into()),
+                Err(e) if e.kind() == ErrorKind::NotFound => return Err(ConverterError::FfmpegNotFound.into()),
+                Err(e) => return Err(e.into()),
             }
 
-            let manual_path = Text::new("Enter video file path or URL:")
-                .prompt()?;
+            let volume = compare::volumedetect(&path).await;
+            samples.push(Sample { label, bitrate_kbps, path, volume });
+        }
 
-            return Ok(manual_path);
+        println!();
+        compare::print(&samples);
+        println!();
+        match &self.config.player {
+            Some(player) if std::io::stdin().is_terminal() => self.play_samples(player, &samples).await,
+            Some(_) => {}
+            None => println!("{}", "ℹ️  Set PLAYER in the config (e.g. PLAYER=mpv) to listen to the samples here".blue()),
         }
 
-        println!("{}", format!("📹 Found {} video file(s)", video_files.len()).blue());
+        for sample in &samples {
+            let _ = fs::remove_file(&sample.path);
+        }
+        Ok(())
+    }
 
-        let mut choices: Vec<String> = video_files.iter().map(|vf| vf.to_string()).collect();
-        choices.push("📝 Enter file path or URL manually".to_string());
+    /// Offer to play each `--compare` sample until the user is done listening
+    async fn play_samples(&self, player: &str, samples: &[Sample]) {
+        let mut choices: Vec<String> = samples
+            .iter()
+            .map(|sample| format!("▶️  Play {} ({} kbps)", sample.label, sample.bitrate_kbps))
+            .collect();
+        choices.push("✅ Done".to_string());
 
-        let selection = Select::new("Select a video file to convert:", choices)
-            .prompt()?;
+        loop {
+            let Ok(choice) = Select::new("Listen to a sample:", choices.clone()).prompt() else {
+                return;
+            };
+            let Some(sample) = choices.iter().position(|c| *c == choice).and_then(|index| samples.get(index)) else {
+                return;
+            };
+            match compare::play(player, &sample.path).await {
+                Ok(true) => {}
+                Ok(false) => println!("{}", format!("⚠️  The player '{}' reported an error", player).yellow()),
+                Err(e) => {
+                    println!("{}", format!("⚠️  Could not start the player '{}': {}", player, e).yellow());
+                    return;
+                }
+            }
+        }
+    }
 
-        if selection == "📝 Enter file path or URL manually" {
-            let manual_path = Text::new("Enter video file path or URL:")
-                .prompt()?;
-            Ok(manual_path)
+    /// Probe a single input and print its audio summary
+    async fn print_probe(&self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let input = if Self::is_url(input) { input.to_string() } else { expand_tilde(input) };
+        let info = self.probe_cache.probe(&input, self.headers_for(&input)).await?;
+
+        if self.args.json {
+            let mut summary = probe::summary_json(&input, &info);
+            if self.args.fingerprint {
+                summary["fingerprint"] = fingerprint::compute(&input).await.into();
+            }
+            println!("{}", serde_json::to_string_pretty(&summary)?);
         } else {
-            // Find the selected video file
-            for video_file in video_files {
-                if video_file.to_string() == selection {
-                    return Ok(video_file.name.clone());
-                }
+            probe::print_summary(&input, &info);
+            if self.args.fingerprint {
+                self.print_fingerprint(&input).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the discovered video files, one per line, with their length when ffprobe is available
+    async fn list_video_files(&self, roots: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        let (video_files, _) = self.filter_video_files(self.get_video_files(roots)?);
+        let mut probing = true;
+        for video_file in video_files {
+            let duration = match probing {
+                true => match self.probe_cache.probe(&video_file.path, &[]).await {
+                    Ok(info) => info.duration,
+                    // Without ffprobe every file would fail the same way, so stop trying
+                    Err(ConverterError::ProbeFailed(reason)) if reason == "ffprobe not found" => {
+                        probing = false;
+                        None
+                    }
+                    Err(_) => None,
+                },
+                false => None,
+            };
+            match duration {
+                Some(duration) => println!("{} [{}]", video_file, probe::format_timestamp(duration)),
+                None => println!("{}", video_file),
             }
-            Err("Selected file not found".into())
         }
+        Ok(())
     }
 
     /// Main application logic
     pub async fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        // The benchmark needs no input file, so it skips everything else
+        if self.args.benchmark {
+            return benchmark::run().await;
+        }
+
+        if let Some(input) = &self.args.probe {
+            return self.print_probe(input).await;
+        }
+
+        if let Some(socket_path) = &self.args.serve {
+            #[cfg(unix)]
+            return self.serve(socket_path).await;
+            #[cfg(not(unix))]
+            return Err(format!("Cannot serve on {}: --serve needs Unix domain sockets", socket_path).into());
+        }
+
+        if let Some(queue_dir) = &self.args.queue_dir {
+            let queue_dir = match queue_dir.as_str() {
+                "" => self.config.queue_dir.clone().ok_or("--queue-dir needs a folder, or QUEUE_DIR in the config")?,
+                dir => expand_tilde(dir),
+            };
+            return self.run_queue(&queue_dir).await;
+        }
+
+        // List mode is meant for scripting, so skip the banner and prompts
+        if self.args.list {
+            let roots = match &self.args.dir {
+                Some(dir) => vec![expand_tilde(dir)],
+                None => self.config.default_dirs.clone(),
+            };
+            return self.list_video_files(&roots).await;
+        }
+
         // Print header
         println!("{}", "╔══════════════════════════════════════════╗".cyan());
         println!("{}", "║          Video to Audio Converter       ║".cyan());
@@ -232,64 +3029,349 @@ impl VideoConverter {
         println!();
 
         // Get working directory
-        let root_dir = self.ask_directory()?;
-        println!("{}", format!("🗂️  Working in: {}", root_dir).blue());
+        let roots = match &self.args.dir {
+            Some(dir) => vec![expand_tilde(dir)],
+            None => self.ask_directory()?,
+        };
+        println!("{}", format!("🗂️  Working in: {}", roots.join(", ")).blue());
+
+        // Get video files across the chosen directories
+        let (video_files, filtered_out) = self.filter_video_files(self.get_video_files(&roots)?);
+        if filtered_out > 0 {
+            println!("{}", format!("🔎 Filtered out {} file(s) by name pattern or size", filtered_out).blue());
+        }
+
+        if self.args.estimate {
+            return self.estimate_batch(&video_files).await;
+        }
+
+        if self.args.plan {
+            return self.print_plan(&video_files, &roots).await;
+        }
+
+        if self.args.batch {
+            return self.run_batch(&video_files, &roots).await;
+        }
 
-        // Get video files in directory
-        let video_files = self.get_video_files(&root_dir)?;
+        // Let user select files (resolved against the root directory)
+        let mut selected = self.select_video_files(&video_files, &roots)?;
+        if self.config.recent_files > 0 {
+            for path in selected.iter().filter(|path| Path::new(path).is_file()) {
+                // Losing the history isn't worth interrupting the conversion for
+                let _ = RecentFiles::load().record(path, self.config.recent_files);
+            }
+        }
 
-        // Let user select file
-        let selected_file = self.select_video_file(&video_files, &root_dir)?;
+        // Several ticked files are converted as a batch; one keeps the interactive single-file flow
+        if selected.len() > 1 && self.args.compare.is_some() {
+            println!("{}", "ℹ️  --compare samples one file, so the first one picked is used".blue());
+            selected.truncate(1);
+        }
+        if selected.len() > 1 {
+            let picked = Self::picked_video_files(&video_files, &selected);
+            return self.run_batch(&picked, &roots).await;
+        }
+        let selected_input = selected.remove(0);
 
-        // Determine full input path
-        let full_input_path = if Self::is_url(&selected_file) || Path::new(&selected_file).is_absolute() {
-            selected_file
+        // Pasted data URIs are decoded to a temp file that is removed when this is dropped
+        let decoded = if data_uri::is_data_uri(&selected_input) {
+            match data_uri::decode_to_temp(&selected_input) {
+                Ok(decoded) => Some(decoded),
+                Err(e) => {
+                    println!("{}", symbols::line(Status::Failure, e).red());
+                    return Ok(());
+                }
+            }
         } else {
-            Path::new(&root_dir).join(&selected_file).to_string_lossy().to_string()
+            None
+        };
+        let full_input_path = match &decoded {
+            Some(decoded) => decoded.path_str(),
+            None => selected_input.clone(),
         };
 
         // Check if local file exists
         if !Self::is_url(&full_input_path) {
             let input_path = Path::new(&full_input_path);
             if !input_path.exists() {
-                println!("{}", format!("❌ File not found: {}", full_input_path).red());
+                println!("{}", symbols::line(Status::Failure, format!("File not found: {}", full_input_path)).red());
                 return Ok(());
             }
         }
 
-        // Generate output path
-        let output_path = self.get_output_path(&full_input_path);
+        if let Some(bitrates) = &self.args.compare {
+            return self.compare_bitrates(&full_input_path, bitrates).await;
+        }
+
+        // Choose audio settings
+        let options = self.choose_options(true).await?;
+        let options = self.source_options(&full_input_path, &options).await;
+
+        // Generate output path (unless one was given)
+        let output_path = match &self.args.output {
+            Some(output) if options.preview_seconds.is_some() => Self::with_stem_suffix(&expand_tilde(output), "_preview"),
+            Some(output) => expand_tilde(output),
+            None => self.get_output_path(&selected_input, &roots, &options, 1),
+        };
+        if let (Some(copy), Some(_)) = (&options.stream_copy, &self.args.output) {
+            let extension = Path::new(&output_path).extension().and_then(|e| e.to_str()).unwrap_or_default();
+            if !extension.eq_ignore_ascii_case(copy.extension) {
+                println!("{}", format!(
+                    "⚠️  The copied {} audio is written as .{} data, but the output ends in .{}",
+                    copy.codec.to_uppercase(), copy.extension, extension
+                ).yellow());
+            }
+        }
 
         println!();
-        println!("{}", format!("📁 Input: {}", full_input_path).blue());
+        match &decoded {
+            Some(decoded) => println!("{}", format!(
+                "📁 Input: pasted data URI ({}, {:.2} MB)",
+                decoded.mime,
+                decoded.size_bytes as f64 / (1024.0 * 1024.0)
+            ).blue()),
+            None => println!("{}", format!("📁 Input: {}", full_input_path).blue()),
+        }
         println!("{}", format!("💾 Output: {}", output_path).blue());
+        if Self::is_url(&full_input_path) {
+            for header in self.redacted_headers() {
+                println!("{}", format!("🔑 Header: {}", header).blue());
+            }
+        }
         println!();
 
-        // Perform conversion
-        match self.convert_to_mp3(&full_input_path, &output_path).await {
-            Ok(true) => {
-                println!("{}", format!("✅ Conversion finished: {}", output_path).green());
-
-                // Show output file size
-                if let Some(size_mb) = self.get_file_size_mb(&output_path) {
-                    println!("{}", format!("📊 Output file size: {:.2} MB", size_mb).yellow());
+        // Work out what to extract (the whole file unless chapters or segments were picked)
+        let jobs = if let Some(segments_file) = &self.args.segments {
+            self.segment_jobs(&full_input_path, &output_path, segments_file).await?
+        } else if self.args.chapters {
+            self.choose_chapters(&full_input_path, &output_path).await?
+        } else if let Some(percent) = self.args.sample {
+            match self.sample_range(&full_input_path, percent).await {
+                Some(range) => {
+                    println!("{}", format!(
+                        "✂️  Sampling {}% of the video: {}–{}",
+                        percent, probe::format_timestamp(range.start), probe::format_timestamp(range.end)
+                    ).blue());
+                    vec![(self.chapter_output_path(&output_path, "sample"), vec![range])]
+                }
+                None => {
+                    println!("{}", symbols::line(Status::Failure, "Could not determine the video's duration, which --sample needs").red());
+                    return Ok(());
                 }
             }
-            Ok(false) => {
-                println!("{}", "❌ Conversion failed!".red());
-                std::process::exit(1);
-            }
-            Err(e) => {
-                if e.to_string().contains("No such file or directory") 
-                    || e.to_string().contains("program not found") {
-                    println!("{}", "❌ FFmpeg not found. Please make sure FFmpeg is installed and in your PATH.".red());
-                } else {
-                    println!("{}", format!("❌ Error during conversion: {}", e).red());
+        } else {
+            vec![(output_path, Vec::new())]
+        };
+
+        let input_size_mb = self.get_file_size_mb(&full_input_path);
+
+        // Perform conversion; after a failure, interactive runs can retry, adjust or skip
+        let started = Instant::now();
+        let mut options = options;
+        let mut succeeded = 0;
+        let mut failures = 0;
+        'jobs: for (mut output_path, ranges) in jobs {
+            loop {
+                let error = match self.convert_to_mp3(&full_input_path, &output_path, &ranges, &options, None).await {
+                    Ok(Conversion::Succeeded) => {
+                        succeeded += 1;
+                        match options.segment_seconds {
+                            Some(_) => {
+                                let segments = self.produced_files(&output_path, &options);
+                                println!("{}", symbols::line(Status::Success, format!("Conversion finished: {} segment(s)", segments.len())).green());
+                                for segment in &segments {
+                                    println!("{}", format!("   • {}", segment).green());
+                                }
+                            }
+                            None => println!("{}", symbols::line(Status::Success, format!("Conversion finished: {}", output_path)).green()),
+                        }
+
+                        // Show output file size
+                        let output_size_mb = self.output_size_mb(&output_path, &options);
+                        if let Some(size_mb) = output_size_mb {
+                            println!("{}", format!("📊 Output file size: {:.2} MB", size_mb).yellow());
+                        }
+                        self.report_extra_outputs(&output_path, &options);
+                        self.warn_if_larger(input_size_mb, output_size_mb);
+                        self.run_hook(true, &full_input_path, &output_path, output_size_mb).await;
+                        break;
+                    }
+                    Ok(Conversion::Skipped) => break,
+                    Err(ConverterError::Cancelled) => return Err(ConverterError::Cancelled.into()),
+                    Ok(Conversion::Failed) => symbols::line(Status::Failure, "Conversion failed!"),
+                    Err(e @ (ConverterError::InputUnreadable { .. } | ConverterError::FfmpegNotFound)) => symbols::line(Status::Failure, e),
+                    Err(e) => symbols::line(Status::Failure, format!("Error during conversion: {}", e)),
+                };
+                println!("{}", error.red());
+
+                let choice = self.ask_after_failure(&mut options, &mut output_path).await?;
+                if choice == AfterFailure::Retry {
+                    continue;
+                }
+                failures += 1;
+                self.run_hook(false, &full_input_path, &output_path, None).await;
+                if choice == AfterFailure::Quit {
+                    break 'jobs;
                 }
-                std::process::exit(1);
+                break;
             }
         }
 
+        self.notify_finished(succeeded, failures, started).await;
+
+        // Exiting skips destructors, so clean up the decoded temp file first
+        drop(decoded);
+        if failures > 0 {
+            tempdir::exit(1);
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the system temp directory that no other test uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("convert-test-{}-{}", std::process::id(), name))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn check_readable_reports_unreadable_input() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("unreadable.mp4");
+        fs::write(&path, b"not really a video").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root can open the file whatever its mode, so there's nothing to check
+        if fs::File::open(&path).is_ok() {
+            fs::remove_file(&path).unwrap();
+            return;
+        }
+
+        let result = VideoConverter::check_readable(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ConverterError::InputUnreadable { .. })), "got {:?}", result);
+    }
+
+    fn stem_of(url: &str) -> Option<String> {
+        VideoConverter::url_file_stem(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn url_file_stem_decodes_encoded_spaces() {
+        assert_eq!(stem_of("https://example.com/videos/My%20Lecture.mp4").as_deref(), Some("My Lecture"));
+        assert_eq!(stem_of("https://example.com/%4D%79%20Talk.mkv").as_deref(), Some("My Talk"));
+    }
+
+    #[test]
+    fn url_file_stem_decodes_non_ascii() {
+        assert_eq!(stem_of("https://example.com/%C3%A9t%C3%A9.mp4").as_deref(), Some("été"));
+        assert_eq!(stem_of("https://example.com/видео.mp4").as_deref(), Some("видео"));
+    }
+
+    #[test]
+    fn url_file_stem_ignores_query_and_fragment() {
+        assert_eq!(stem_of("https://example.com/a/talk.mp4?token=abc.def#t=10").as_deref(), Some("talk"));
+        assert_eq!(stem_of("https://example.com/watch?v=123").as_deref(), Some("watch"));
+    }
+
+    #[test]
+    fn url_file_stem_keeps_encoded_slashes_in_one_segment() {
+        assert_eq!(stem_of("https://example.com/dir/a%2Fb.mp4").as_deref(), Some("a/b"));
+    }
+
+    #[test]
+    fn url_file_stem_needs_a_path() {
+        assert_eq!(stem_of("https://example.com"), None);
+        assert_eq!(stem_of("https://example.com/"), None);
+        assert_eq!(stem_of("https://example.com/?v=1"), None);
+    }
+
+    #[tokio::test]
+    async fn output_on_an_existing_directory_fails_before_ffmpeg_runs() {
+        let input = temp_path("lecture.mp4");
+        let output = temp_path("lecture.mp3");
+        fs::write(&input, b"not really a video").unwrap();
+        fs::create_dir(&output).unwrap();
+
+        let converter = VideoConverter::new(Args::default()).unwrap();
+        let result = converter
+            .convert_to_mp3(input.to_str().unwrap(), output.to_str().unwrap(), &[], &ConversionOptions::default(), None)
+            .await;
+
+        fs::remove_file(&input).unwrap();
+        fs::remove_dir_all(&output).unwrap();
+        // Checked before anything else happens, so no ffmpeg (or ffprobe) error can come first
+        match result {
+            Err(ConverterError::OutputIsDirectory { path }) => assert_eq!(path, output.to_str().unwrap()),
+            other => panic!("expected OutputIsDirectory, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logged_command_hides_header_values_and_the_key() {
+        let args: Vec<String> = [
+            "-headers", "Authorization: Bearer secret\r\nCookie: session=abc\r\n",
+            "-decryption_key", "0123456789abcdef",
+            "-i", "https://example.com/video.mp4", "-y", "out.part.mp3",
+        ].iter().map(|arg| arg.to_string()).collect();
+
+        assert_eq!(
+            VideoConverter::redacted_command(&args),
+            "ffmpeg -headers Authorization: <redacted>\\r\\nCookie: <redacted> -decryption_key <redacted> \
+             -i https://example.com/video.mp4 -y out.part.mp3"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn long_names_fit_with_their_lock_part_and_log_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Stand-ins for ffmpeg and ffprobe: ffmpeg writes its last argument, the output
+        let tools = temp_path("tools");
+        fs::create_dir_all(&tools).unwrap();
+        let scripts = [
+            ("ffmpeg", "#!/bin/sh\nfor arg in \"$@\"; do last=\"$arg\"; done\nhead -c 1000 /dev/zero > \"$last\"\n"),
+            ("ffprobe", "#!/bin/sh\necho '{\"streams\":[],\"format\":{\"duration\":\"10.0\"}}'\n"),
+        ];
+        for (name, script) in scripts {
+            let path = tools.join(name);
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let path = std::env::var("PATH").unwrap_or_default();
+        std::env::set_var("PATH", format!("{}:{}", tools.display(), path));
+
+        let dir = temp_path("long-names");
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.mp4");
+        fs::write(&input, b"not really a video").unwrap();
+
+        let args = Args { keep_logs: Some(KeepLogs::Always), ..Args::default() };
+        let converter = VideoConverter::new(args).unwrap();
+        let file_name = converter.output_filename(&"a".repeat(300), "mp3");
+        let output = dir.join(&file_name);
+        let result = converter
+            .convert_to_mp3(input.to_str().unwrap(), output.to_str().unwrap(), &[], &ConversionOptions::default(), None)
+            .await;
+
+        let mut left: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&tools).unwrap();
+
+        assert!(result.is_ok(), "got {:?}", result);
+        // The output leaves room for the sidecar suffixes, so none of them had to be shortened
+        let log_name = format!("{}.log", file_name);
+        assert!(log_name.len() <= naming::MAX_FILENAME_BYTES);
+        assert_eq!(left, vec![file_name, log_name, "input.mp4".to_string()]);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::error::ConverterError;
+use crate::tempdir;
+use base64::Engine;
+use std::fs;
+use std::path::PathBuf;
+
+/// Largest decoded payload accepted from a data URI (50 MB)
+pub const MAX_DATA_URI_BYTES: usize = 50 * 1024 * 1024;
+
+/// A data URI payload written to a temporary file, removed again on drop
+pub struct DecodedInput {
+    pub path: PathBuf,
+    pub mime: String,
+    pub size_bytes: usize,
+}
+
+impl Drop for DecodedInput {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Check whether the input is a `data:` URI
+pub fn is_data_uri(input: &str) -> bool {
+    input.get(..5).is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+}
+
+/// Decode a `data:[<mime>][;base64],<payload>` URI into a temporary file
+pub fn decode_to_temp(input: &str) -> Result<DecodedInput, ConverterError> {
+    let invalid = |reason: &str| ConverterError::InvalidDataUri(reason.to_string());
+
+    let (header, payload) = input[5..]
+        .split_once(',')
+        .ok_or_else(|| invalid("missing ',' before the payload"))?;
+
+    let mut params = header.split(';');
+    let mime = params.next().filter(|m| !m.is_empty()).unwrap_or("application/octet-stream");
+    let is_base64 = params.any(|p| p.eq_ignore_ascii_case("base64"));
+
+    // Check the size before decoding so a huge paste never gets allocated twice
+    let estimated = if is_base64 { payload.len() / 4 * 3 } else { payload.len() };
+    if estimated > MAX_DATA_URI_BYTES {
+        return Err(ConverterError::InvalidDataUri(format!(
+            "payload is about {:.1} MB, the limit is {} MB",
+            estimated as f64 / (1024.0 * 1024.0),
+            MAX_DATA_URI_BYTES / (1024 * 1024)
+        )));
+    }
+
+    let bytes = if is_base64 {
+        // Pasted base64 is often wrapped across lines
+        let compact: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD
+            .decode(compact)
+            .map_err(|e| ConverterError::InvalidDataUri(format!("bad base64 payload: {}", e)))?
+    } else {
+        percent_encoding::percent_decode_str(payload).collect()
+    };
+
+    if bytes.is_empty() {
+        return Err(invalid("payload is empty"));
+    }
+
+    let path = tempdir::path(&format!("pasted.{}", extension_for_mime(mime)))?;
+    fs::write(&path, &bytes)?;
+
+    Ok(DecodedInput {
+        path,
+        mime: mime.to_string(),
+        size_bytes: bytes.len(),
+    })
+}
+
+/// Pick a file extension for the temp file so ffmpeg's format guess has a hint
+fn extension_for_mime(mime: &str) -> &'static str {
+    match mime.to_ascii_lowercase().as_str() {
+        "video/mp4" => "mp4",
+        "video/webm" => "webm",
+        "video/quicktime" => "mov",
+        "video/x-matroska" => "mkv",
+        "video/x-msvideo" => "avi",
+        "video/x-flv" => "flv",
+        "video/3gpp" => "3gp",
+        "audio/mpeg" => "mp3",
+        "audio/mp4" => "m4a",
+        "audio/ogg" => "ogg",
+        "audio/wav" | "audio/x-wav" => "wav",
+        _ => "bin",
+    }
+}
+
+impl DecodedInput {
+    /// The temp file path as a string for ffmpeg
+    pub fn path_str(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
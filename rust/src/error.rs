@@ -0,0 +1,114 @@
+use crate::ffmpeg;
+use crate::format::{AudioFormat, Container};
+use crate::probe::format_timestamp;
+use std::fmt;
+use std::io;
+
+/// Errors that can occur while converting a video
+#[derive(Debug)]
+pub enum ConverterError {
+    /// The input file exists but cannot be opened for reading
+    InputUnreadable { path: String },
+    /// The input file has no content, usually an interrupted download
+    EmptyInput { path: String },
+    /// The output path names an existing directory, e.g. a folder called `lecture.mp3`
+    OutputIsDirectory { path: String },
+    /// A config file given with `--config` is missing or unreadable
+    ConfigUnreadable { path: String, reason: String },
+    /// The ffmpeg executable could not be launched
+    FfmpegNotFound,
+    /// A pasted `data:` URI was malformed or too large
+    InvalidDataUri(String),
+    /// ffmpeg was built without the encoder the chosen format needs
+    EncoderMissing { encoder: String },
+    /// No output format was given and the `--output` extension doesn't name one
+    UnknownOutputFormat { extension: String },
+    /// `--output-template` uses an unknown placeholder or yields the wrong extension
+    InvalidOutputTemplate(String),
+    /// ffprobe could not read the input or produced unusable output
+    ProbeFailed(String),
+    /// A `--segments` timestamps file is missing or malformed
+    InvalidSegmentFile { path: String, reason: String },
+    /// `--container` can't hold the chosen codec
+    IncompatibleContainer { format: AudioFormat, container: Container },
+    /// The input is longer than `--max-duration` and `--force` wasn't given
+    TooLong { duration: f64, limit: f64 },
+    /// The run was cancelled (Ctrl+C or a front-end) while converting
+    Cancelled,
+    /// ffmpeg was ended by a signal it didn't send itself, most often SIGKILL from the out-of-memory killer
+    Killed { signal: i32 },
+    /// Any other I/O failure
+    Io(io::Error),
+}
+
+impl fmt::Display for ConverterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConverterError::InputUnreadable { path } => {
+                write!(f, "Cannot read {}: permission denied", path)
+            }
+            ConverterError::EmptyInput { path } => write!(
+                f,
+                "Input file is empty (0 bytes): {}. It was probably an interrupted download; try downloading it again.",
+                path
+            ),
+            ConverterError::OutputIsDirectory { path } => write!(
+                f,
+                "Output path is a directory: {}. Rename or move that folder, or pick another name with --output or --output-template.",
+                path
+            ),
+            ConverterError::ConfigUnreadable { path, reason } => {
+                write!(f, "Cannot read config file {}: {}", path, reason)
+            }
+            ConverterError::FfmpegNotFound => write!(
+                f,
+                "FFmpeg not found. Please make sure FFmpeg is installed and in your PATH."
+            ),
+            ConverterError::EncoderMissing { encoder } => write!(
+                f,
+                "FFmpeg has no '{}' encoder. Use --fallback-format to convert to another format instead.",
+                encoder
+            ),
+            ConverterError::InvalidDataUri(reason) => write!(f, "Invalid data URI: {}", reason),
+            ConverterError::UnknownOutputFormat { extension } => write!(
+                f,
+                "Cannot tell the output format from the extension '{}'. Use --format to choose one.",
+                extension
+            ),
+            ConverterError::InvalidOutputTemplate(reason) => write!(f, "Invalid output template: {}", reason),
+            ConverterError::ProbeFailed(reason) => write!(f, "Could not probe input: {}", reason),
+            ConverterError::InvalidSegmentFile { path, reason } => {
+                write!(f, "Invalid timestamps file {}: {}", path, reason)
+            }
+            ConverterError::IncompatibleContainer { format, container } => write!(
+                f,
+                "{} audio can't be stored in a {} container. Containers that can hold it: {}",
+                format.name(),
+                container,
+                format.containers().iter().map(Container::name).collect::<Vec<_>>().join(", ")
+            ),
+            ConverterError::Cancelled => write!(f, "Cancelled"),
+            ConverterError::Killed { signal } => write!(
+                f,
+                "FFmpeg was killed by signal {} ({})",
+                signal,
+                ffmpeg::signal_name(*signal)
+            ),
+            ConverterError::TooLong { duration, limit } => write!(
+                f,
+                "Input is {} long, over the --max-duration limit of {}. Use --force to convert it anyway.",
+                format_timestamp(*duration),
+                format_timestamp(*limit)
+            ),
+            ConverterError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConverterError {}
+
+impl From<io::Error> for ConverterError {
+    fn from(e: io::Error) -> Self {
+        ConverterError::Io(e)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::options::ConversionOptions;
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::process::Command;
+
+/// Length of the clip encoded to measure speed, in seconds
+const CALIBRATION_SECONDS: f64 = 30.0;
+
+/// Encode a short clip from the middle of an input with the chosen settings and
+/// return the speed as a multiple of realtime, or `None` if the encode failed
+///
+/// The result is discarded with the null muxer, so only decoding, filtering and
+/// encoding are timed.
+pub async fn measure_speed(input_path: &str, duration: f64, options: &ConversionOptions) -> Option<f64> {
+    let length = duration.min(CALIBRATION_SECONDS);
+    let start = (duration - length) / 2.0;
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-nostdin", "-hide_banner", "-loglevel", "error"])
+        .args(&options.input_args)
+        .args(["-ss", &format!("{:.3}", start), "-t", &format!("{:.3}", length)])
+        .args(["-i", input_path, "-vn"]);
+
+    let filters = options.audio_filters();
+    if !filters.is_empty() {
+        command.args(["-af", &filters.join(",")]);
+    }
+    command.args(["-acodec", &options.encoder]);
+    if !options.format.is_lossless() {
+        command.args(["-ab", &format!("{}k", options.bitrate_kbps)]);
+    }
+
+    let started = Instant::now();
+    let status = command
+        .args(["-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .ok()?;
+    let elapsed = started.elapsed().as_secs_f64();
+
+    (status.success() && elapsed > 0.0).then(|| length / elapsed)
+}
+
+/// A rounded time such as `~18 min`, `~2 h 10 min` or `under a minute`
+pub fn format_estimate(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as u64;
+    match minutes {
+        0 => "under a minute".to_string(),
+        1..=59 => format!("~{} min", minutes),
+        _ => {
+            // Ten-minute steps are as precise as a ballpark gets at this scale
+            let minutes = (minutes + 5) / 10 * 10;
+            match minutes % 60 {
+                0 => format!("~{} h", minutes / 60),
+                rest => format!("~{} h {} min", minutes / 60, rest),
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::process::ExitStatus;
+use tokio::process::Command;
+use url::Url;
+
+/// Input flags for HLS and DASH manifests: segments may come over any of these
+/// protocols, and dropped connections are retried instead of ending the stream early
+const MANIFEST_INPUT_ARGS: &[&str] = &[
+    "-protocol_whitelist", "file,http,https,tcp,tls,crypto",
+    "-reconnect", "1",
+    "-reconnect_streamed", "1",
+    "-reconnect_delay_max", "5",
+];
+
+/// Input flags for recording a plain HTTP(S) live stream (e.g. Icecast): reconnect when the connection drops
+const LIVE_INPUT_ARGS: &[&str] = &[
+    "-reconnect", "1",
+    "-reconnect_streamed", "1",
+    "-reconnect_delay_max", "5",
+];
+
+/// Whether an input is an HTTP(S) URL to an HLS (`.m3u8`) or DASH (`.mpd`) manifest
+pub fn is_streaming_manifest(input: &str) -> bool {
+    Url::parse(input).is_ok_and(|url| {
+        let path = url.path().to_lowercase();
+        matches!(url.scheme(), "http" | "https") && (path.ends_with(".m3u8") || path.ends_with(".mpd"))
+    })
+}
+
+/// Whether an input is an HTTP(S) URL to an HLS (`.m3u8`) playlist, which names its own key URIs
+pub fn is_hls_playlist(input: &str) -> bool {
+    is_streaming_manifest(input) && Url::parse(input).is_ok_and(|url| url.path().to_lowercase().ends_with(".m3u8"))
+}
+
+/// Extra input flags ffmpeg and ffprobe need to read this input reliably
+pub fn input_args_for(input: &str) -> &'static [&'static str] {
+    if is_streaming_manifest(input) {
+        MANIFEST_INPUT_ARGS
+    } else {
+        &[]
+    }
+}
+
+/// Input flags for `--record-duration`: the usual ones, plus reconnects for any HTTP(S) stream
+///
+/// Other protocols (RTMP, RTSP, ...) don't take the reconnect options.
+pub fn recording_input_args(input: &str) -> &'static [&'static str] {
+    let is_http = Url::parse(input).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    match input_args_for(input) {
+        [] if is_http => LIVE_INPUT_ARGS,
+        args => args,
+    }
+}
+
+/// Have a command's process start at a lower scheduling priority (`--nice`)
+#[cfg(unix)]
+pub fn lower_priority(command: &mut Command, niceness: i32) {
+    // SAFETY: the closure only makes the setpriority syscall, which is async-signal-safe
+    unsafe {
+        command.pre_exec(move || {
+            // Failing to lower the priority isn't worth failing the conversion over
+            libc::setpriority(libc::PRIO_PROCESS as _, 0, niceness);
+            Ok(())
+        });
+    }
+}
+
+/// Windows has priority classes instead of nice levels, so any level means "below normal"
+#[cfg(windows)]
+pub fn lower_priority(command: &mut Command, _niceness: i32) {
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+    command.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+}
+
+/// No priority control elsewhere
+#[cfg(not(any(unix, windows)))]
+pub fn lower_priority(_command: &mut Command, _niceness: i32) {}
+
+/// Pause (SIGSTOP) or resume (SIGCONT) a running ffmpeg process, returning whether it worked
+#[cfg(unix)]
+pub fn set_paused(pid: u32, paused: bool) -> bool {
+    let signal = if paused { libc::SIGSTOP } else { libc::SIGCONT };
+    // SAFETY: kill() has no memory-safety preconditions; a stale pid just yields an error
+    unsafe { libc::kill(pid as libc::pid_t, signal) == 0 }
+}
+
+/// Pausing needs job-control signals, which this platform doesn't have
+#[cfg(not(unix))]
+pub fn set_paused(_pid: u32, _paused: bool) -> bool {
+    false
+}
+
+/// The signal that ended a process, if it didn't exit on its own
+#[cfg(unix)]
+pub fn termination_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+/// Processes always exit with a code here
+#[cfg(not(unix))]
+pub fn termination_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Conventional name of a signal number, e.g. `SIGKILL` for 9
+#[cfg(unix)]
+pub fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGINT => "SIGINT",
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        _ => "unknown signal",
+    }
+}
+
+#[cfg(not(unix))]
+pub fn signal_name(_signal: i32) -> &'static str {
+    "unknown signal"
+}
+
+/// Names of all encoders the installed ffmpeg was built with, or `None` if ffmpeg can't be run
+pub async fn available_encoders() -> Option<HashSet<String>> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .await
+        .ok()?;
+
+    // Encoder lines look like " A..... libmp3lame   libmp3lame MP3 (MPEG audio layer 3)"
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Decode the last `seconds` of a file's audio and explain why it looks truncated or corrupt
+///
+/// Partially downloaded files often only fail near their end, so this catches them before a
+/// long conversion does. `None` means the end decoded cleanly, or ffmpeg couldn't be run (which
+/// the conversion itself then reports).
+pub async fn check_tail(input: &str, seconds: u32) -> Option<String> {
+    let output = Command::new("ffmpeg")
+        .args(["-nostdin", "-hide_banner", "-v", "error", "-sseof", &format!("-{}", seconds), "-i", input])
+        .args(["-t", &seconds.to_string(), "-vn", "-sn", "-dn", "-progress", "pipe:1", "-f", "null", "-"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .ok()?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if let Some(error) = stderr.lines().map(str::trim).find(|line| !line.is_empty()) {
+        return Some(error.to_string());
+    }
+    if !output.status.success() {
+        return Some(format!("ffmpeg stopped with {}", output.status));
+    }
+
+    // A file cut short still claims its full length, so seeking near the end finds nothing
+    let decoded_us = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix("out_time_us=")?.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+    if decoded_us <= 0 {
+        return Some(format!("no audio could be decoded from the last {} seconds", seconds));
+    }
+    None
+}
+
+/// Whether the installed ffmpeg was built with the SoX resampler, or `None` if ffmpeg can't be run
+pub async fn has_soxr() -> Option<bool> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-buildconf"])
+        .output()
+        .await
+        .ok()?;
+
+    // The configure flags are listed one per line, on stdout or stderr depending on the version
+    let flags = [output.stdout, output.stderr].concat();
+    Some(String::from_utf8_lossy(&flags).contains("--enable-libsoxr"))
+}
+
+/// Hardware acceleration methods the installed ffmpeg supports, or `None` if ffmpeg can't be run
+pub async fn available_hwaccels() -> Option<Vec<String>> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-hwaccels"])
+        .output()
+        .await
+        .ok()?;
+
+    // The first line is the "Hardware acceleration methods:" heading
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
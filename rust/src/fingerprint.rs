@@ -0,0 +1,67 @@
+use std::process::Stdio;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+/// FNV-1a offset basis and prime (64-bit)
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A content-based identifier for the first audio stream of a file
+///
+/// Uses Chromaprint (`chromaprint:<base64>`, the AcoustID fingerprint) when ffmpeg was built
+/// with it, which also matches the same recording encoded differently. Otherwise falls back to
+/// `pcm:<hex>`, a hash of the decoded mono 22.05 kHz samples, which only matches identical audio
+/// but is still independent of the container.
+pub async fn compute(path: &str) -> Option<String> {
+    match chromaprint(path).await {
+        Some(fingerprint) => Some(format!("chromaprint:{}", fingerprint)),
+        None => pcm_hash(path).await.map(|hash| format!("pcm:{:016x}", hash)),
+    }
+}
+
+/// Chromaprint fingerprint from ffmpeg's `chromaprint` muxer, if it has one
+async fn chromaprint(path: &str) -> Option<String> {
+    let output = Command::new("ffmpeg")
+        .args(["-nostdin", "-hide_banner", "-loglevel", "error"])
+        .args(["-i", path, "-map", "0:a:0", "-f", "chromaprint", "-fp_format", "base64", "-"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+
+    let fingerprint = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !fingerprint.is_empty()).then_some(fingerprint)
+}
+
+/// FNV-1a hash of the decoded samples, streamed so long files aren't held in memory
+async fn pcm_hash(path: &str) -> Option<u64> {
+    let mut child = Command::new("ffmpeg")
+        .args(["-nostdin", "-hide_banner", "-loglevel", "error"])
+        .args(["-i", path, "-map", "0:a:0", "-ac", "1", "-ar", "22050", "-f", "s16le", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+    let mut stdout = child.stdout.take()?;
+
+    let mut hash = FNV_OFFSET;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut total = 0;
+    loop {
+        let read = stdout.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+        total += read;
+    }
+
+    let status = child.wait().await.ok()?;
+    (status.success() && total > 0).then_some(hash)
+}
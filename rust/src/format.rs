@@ -0,0 +1,245 @@
+use clap::ValueEnum;
+use std::fmt;
+
+/// Audio formats the converter can produce
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum AudioFormat {
+    /// MP3 encoded with LAME
+    #[default]
+    Mp3,
+    /// AAC in an M4A container (Apple-friendly)
+    Aac,
+    /// Opus in an Ogg container (small files, great for speech)
+    Opus,
+    /// Lossless FLAC (for archiving)
+    Flac,
+    /// Uncompressed 16-bit PCM WAV (for editors and ML pipelines)
+    Wav,
+}
+
+impl AudioFormat {
+    /// Every supported format, in menu order
+    pub const ALL: [AudioFormat; 5] = [AudioFormat::Mp3, AudioFormat::Aac, AudioFormat::Opus, AudioFormat::Flac, AudioFormat::Wav];
+
+    /// Short lowercase name, as accepted by `--format`
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    /// File extension for outputs in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "m4a",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    /// Most channels the format can carry
+    pub fn max_channels(&self) -> u32 {
+        match self {
+            AudioFormat::Mp3 => 2,
+            AudioFormat::Aac | AudioFormat::Opus | AudioFormat::Flac | AudioFormat::Wav => 8,
+        }
+    }
+
+    /// Whether the format ignores bitrate settings
+    pub fn is_lossless(&self) -> bool {
+        matches!(self, AudioFormat::Flac | AudioFormat::Wav)
+    }
+
+    /// ffmpeg encoder used unless a better one is available
+    pub fn default_encoder(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "libmp3lame",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Opus => "libopus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "pcm_s16le",
+        }
+    }
+
+    /// Codec name ffprobe reports for audio in this format
+    pub fn codec_name(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "pcm_s16le",
+        }
+    }
+
+    /// ffmpeg muxer that writes this container
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Aac => "ipod",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+
+    /// Containers that can hold this codec with `--container`
+    pub fn containers(&self) -> Vec<Container> {
+        Container::ALL.into_iter().filter(|container| container.supports(*self)).collect()
+    }
+
+    /// Muxer arguments needed to get the right container
+    pub fn container_args(&self) -> &'static [&'static str] {
+        match self {
+            AudioFormat::Mp3 | AudioFormat::Opus | AudioFormat::Flac | AudioFormat::Wav => &[],
+            // The ipod muxer writes a proper M4A that QuickTime/iTunes accept
+            AudioFormat::Aac => &["-f", "ipod", "-movflags", "+faststart"],
+        }
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioFormat::Mp3 => write!(f, "MP3"),
+            AudioFormat::Aac => write!(f, "AAC (.m4a)"),
+            AudioFormat::Opus => write!(f, "Opus (.opus)"),
+            AudioFormat::Flac => write!(f, "FLAC (lossless)"),
+            AudioFormat::Wav => write!(f, "WAV (16-bit PCM)"),
+        }
+    }
+}
+
+/// Where a copied (not re-encoded) source audio stream is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamCopy {
+    /// Source codec as ffprobe names it, e.g. `aac` or `ac3`
+    pub codec: String,
+    pub extension: &'static str,
+    pub muxer: &'static str,
+    pub container_args: &'static [&'static str],
+}
+
+impl StreamCopy {
+    /// The usual file type for a codec; anything unknown goes into Matroska, which holds nearly every codec
+    pub fn for_codec(codec: &str) -> Self {
+        let (extension, muxer, container_args): (_, _, &'static [&'static str]) = match codec {
+            "aac" | "alac" => ("m4a", "ipod", &["-f", "ipod", "-movflags", "+faststart"]),
+            "mp3" => ("mp3", "mp3", &["-f", "mp3"]),
+            "ac3" => ("ac3", "ac3", &["-f", "ac3"]),
+            "eac3" => ("eac3", "eac3", &["-f", "eac3"]),
+            "dts" => ("dts", "dts", &["-f", "dts"]),
+            "opus" => ("opus", "opus", &["-f", "opus"]),
+            "vorbis" => ("ogg", "ogg", &["-f", "ogg"]),
+            "flac" => ("flac", "flac", &["-f", "flac"]),
+            c if c == "pcm_u8" || (c.starts_with("pcm_") && c.ends_with("le")) => ("wav", "wav", &["-f", "wav"]),
+            _ => ("mka", "matroska", &["-f", "matroska"]),
+        };
+        Self { codec: codec.to_string(), extension, muxer, container_args }
+    }
+
+    /// Copy into a container chosen with `--container` instead
+    pub fn in_container(codec: &str, container: Container) -> Self {
+        Self {
+            codec: codec.to_string(),
+            extension: container.extension(),
+            muxer: container.muxer(),
+            container_args: container.container_args(),
+        }
+    }
+}
+
+/// Containers `--container` can put the audio in, independently of the codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Container {
+    /// Raw MP3 stream (.mp3)
+    Mp3,
+    /// MPEG-4 audio (.m4a)
+    M4a,
+    /// Matroska audio (.mka), holds any of the codecs
+    Mka,
+    /// Ogg (.ogg)
+    Ogg,
+    /// Native FLAC (.flac)
+    Flac,
+}
+
+impl Container {
+    /// Every container, in help order
+    pub const ALL: [Container; 5] = [Container::Mp3, Container::M4a, Container::Mka, Container::Ogg, Container::Flac];
+
+    /// Short lowercase name, as accepted by `--container`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Container::Mp3 => "mp3",
+            Container::M4a => "m4a",
+            Container::Mka => "mka",
+            Container::Ogg => "ogg",
+            Container::Flac => "flac",
+        }
+    }
+
+    /// File extension for outputs in this container
+    pub fn extension(&self) -> &'static str {
+        self.name()
+    }
+
+    /// ffmpeg muxer that writes this container
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp3 => "mp3",
+            Container::M4a => "ipod",
+            Container::Mka => "matroska",
+            Container::Ogg => "ogg",
+            Container::Flac => "flac",
+        }
+    }
+
+    /// Muxer arguments that select this container regardless of the output extension
+    pub fn container_args(&self) -> &'static [&'static str] {
+        match self {
+            Container::Mp3 => &["-f", "mp3"],
+            Container::M4a => &["-f", "ipod", "-movflags", "+faststart"],
+            Container::Mka => &["-f", "matroska"],
+            Container::Ogg => &["-f", "ogg"],
+            Container::Flac => &["-f", "flac"],
+        }
+    }
+
+    /// Whether ffmpeg can mux this codec into the container
+    pub fn supports(&self, format: AudioFormat) -> bool {
+        match self {
+            Container::Mp3 => format == AudioFormat::Mp3,
+            // The ipod muxer only takes AAC (and ALAC)
+            Container::M4a => format == AudioFormat::Aac,
+            Container::Mka => true,
+            Container::Ogg => matches!(format, AudioFormat::Opus | AudioFormat::Flac),
+            Container::Flac => format == AudioFormat::Flac,
+        }
+    }
+}
+
+impl Container {
+    /// Whether ffmpeg can copy a stream of this codec (ffprobe's name) into the container
+    pub fn holds_codec(&self, codec: &str) -> bool {
+        match self {
+            Container::Mp3 => codec == "mp3",
+            Container::M4a => matches!(codec, "aac" | "alac"),
+            Container::Mka => true,
+            Container::Ogg => matches!(codec, "opus" | "vorbis" | "flac"),
+            Container::Flac => codec == "flac",
+        }
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::ConverterError;
+use crate::format::AudioFormat;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+/// Built-in format data, so the binary works without any file next to it
+const BUNDLED_FORMATS: &str = include_str!("../formats.toml");
+
+/// Recognized video extensions and the output extension → audio format map,
+/// read from `formats.toml`
+#[derive(Debug, Clone)]
+pub struct Formats {
+    video_extensions: Vec<String>,
+    output_extensions: HashMap<String, AudioFormat>,
+}
+
+impl Formats {
+    /// The bundled formats, with `override_path` layered on top if given
+    ///
+    /// An override's `video_extensions` replaces the bundled list, while its
+    /// `[output_extensions]` entries are added to (or replace) the bundled ones.
+    pub fn load(override_path: Option<&str>) -> Result<Self, ConverterError> {
+        let mut formats = Self {
+            video_extensions: Vec::new(),
+            output_extensions: HashMap::new(),
+        };
+        formats.apply(BUNDLED_FORMATS, "bundled formats.toml")?;
+
+        if let Some(path) = override_path {
+            let content = fs::read_to_string(path).map_err(|e| ConverterError::ConfigUnreadable {
+                path: path.to_string(),
+                reason: e.to_string(),
+            })?;
+            formats.apply(&content, path)?;
+        }
+        Ok(formats)
+    }
+
+    /// Whether files with this extension are scanned as videos (case-insensitive)
+    pub fn is_video_extension(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+        self.video_extensions.contains(&extension)
+    }
+
+    /// Video extensions in file order, for messages
+    pub fn video_extensions(&self) -> &[String] {
+        &self.video_extensions
+    }
+
+    /// Format an output file extension stands for (case-insensitive), if any
+    pub fn output_format(&self, extension: &str) -> Option<AudioFormat> {
+        self.output_extensions.get(&extension.to_lowercase()).copied()
+    }
+
+    /// Merge one formats file into these settings
+    fn apply(&mut self, content: &str, source: &str) -> Result<(), ConverterError> {
+        let invalid = |reason: String| ConverterError::ConfigUnreadable {
+            path: source.to_string(),
+            reason,
+        };
+
+        let file: FormatsFile = toml::from_str(content).map_err(|e| invalid(e.to_string().trim_end().to_string()))?;
+        if let Some(extensions) = file.video_extensions {
+            self.video_extensions = extensions
+                .iter()
+                .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+                .filter(|extension| !extension.is_empty())
+                .collect();
+        }
+        for (extension, name) in file.output_extensions {
+            let format = AudioFormat::from_str(&name, true)
+                .map_err(|_| invalid(format!("unknown audio format \"{}\" for .{}", name, extension)))?;
+            self.output_extensions.insert(extension.trim_start_matches('.').to_lowercase(), format);
+        }
+        Ok(())
+    }
+}
+
+/// The contents of a formats.toml file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FormatsFile {
+    video_extensions: Option<Vec<String>>,
+    #[serde(default)]
+    output_extensions: HashMap<String, String>,
+}
@@ -0,0 +1,57 @@
+use crate::paths;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Recently converted files, newest first, kept in the user's state directory
+///
+/// Only local files are remembered; anything that no longer exists is dropped on load.
+pub struct RecentFiles {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+impl RecentFiles {
+    /// Read the history, or start an empty one if there is none yet
+    pub fn load() -> Self {
+        let path = paths::state_dir().map(|dir| dir.join("recent-files"));
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && Path::new(line).is_file())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// Paths in the history, newest first
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Move a file to the top of the history, keeping at most `limit` entries, and save it
+    pub fn record(&mut self, file_path: &str, limit: usize) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // One file can be reached through several relative paths, so store it absolute
+        let absolute = fs::canonicalize(file_path)?.to_string_lossy().to_string();
+
+        self.entries.retain(|entry| *entry != absolute);
+        self.entries.insert(0, absolute);
+        self.entries.truncate(limit);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        fs::write(path, content)
+    }
+}
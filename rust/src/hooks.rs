@@ -0,0 +1,98 @@
+use colored::*;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Fill `{input}`, `{output}` and `{size}` in a hook command, quoting each value for the shell
+///
+/// The template is scanned once, so placeholders appearing inside a substituted value
+/// (a file named `{output}.mp4`) stay as they are.
+pub fn expand_command(template: &str, input: &str, output: &str, size_mb: Option<f64>) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let placeholder = rest.find('}').map(|close| &rest[..=close]).unwrap_or_default();
+        let value = match placeholder {
+            "{input}" => shell_quote(input),
+            "{output}" => shell_quote(output),
+            "{size}" => format!("{:.2}", size_mb.unwrap_or(0.0)),
+            // Not a placeholder, so the brace is kept as written
+            _ => {
+                expanded.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        expanded.push_str(&value);
+        rest = &rest[placeholder.len()..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Quote a value so the shell passes it through as one argument
+#[cfg(unix)]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a value so the shell passes it through as one argument
+#[cfg(not(unix))]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Run a user hook command through the shell, reporting (but never propagating) failures
+pub async fn run(name: &str, command: &str) {
+    println!("{}", format!("🪝 Running {} hook: {}", name, command).blue());
+
+    #[cfg(unix)]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    #[cfg(not(unix))]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    };
+
+    let status = shell
+        .arg(command)
+        .stdin(Stdio::null())
+        .status()
+        .await;
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => match status.code() {
+            Some(code) => println!("{}", format!("⚠️  {} hook exited with code {}", name, code).yellow()),
+            None => println!("{}", format!("⚠️  {} hook was terminated", name).yellow()),
+        },
+        Err(e) => println!("{}", format!("⚠️  Could not run {} hook: {}", name, e).yellow()),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_every_placeholder() {
+        assert_eq!(
+            expand_command("mv {output} /done && echo {size} {input} {other}", "a b.mp4", "a b.mp3", Some(3.5)),
+            "mv 'a b.mp3' /done && echo 3.50 'a b.mp4' {other}"
+        );
+    }
+
+    #[test]
+    fn placeholders_inside_values_are_left_alone() {
+        assert_eq!(
+            expand_command("notify {input} {output}", "{output}'s cut.mp4", "out.mp3", None),
+            r"notify '{output}'\''s cut.mp4' 'out.mp3'"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Keys understood while a conversion is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCommand {
+    /// Pause or resume ffmpeg
+    TogglePause,
+    /// Abandon the current file and move on to the next one
+    Skip,
+    /// Ctrl+C, which raw mode delivers as a key instead of SIGINT
+    Interrupt,
+}
+
+/// Reads single keypresses in raw mode until dropped
+pub struct KeyListener {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    receiver: mpsc::UnboundedReceiver<KeyCommand>,
+}
+
+impl KeyListener {
+    /// Start listening, or return `None` when keyboard control isn't available
+    ///
+    /// Pausing relies on Unix job-control signals, so other platforms and
+    /// non-interactive stdin never enable raw mode.
+    pub fn start() -> Option<Self> {
+        if !cfg!(unix) || !std::io::stdin().is_terminal() || terminal::enable_raw_mode().is_err() {
+            return None;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                    continue;
+                }
+
+                let command = match event::read() {
+                    Ok(Event::Key(KeyEvent { kind: KeyEventKind::Release, .. })) => continue,
+                    Ok(Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers, .. }))
+                        if modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        KeyCommand::Interrupt
+                    }
+                    Ok(Event::Key(KeyEvent { code: KeyCode::Char('p' | 'P'), .. })) => KeyCommand::TogglePause,
+                    Ok(Event::Key(KeyEvent { code: KeyCode::Char('s' | 'S'), .. })) => KeyCommand::Skip,
+                    Ok(_) => continue,
+                    Err(_) => break,
+                };
+
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Some(Self {
+            stop,
+            thread: Some(thread),
+            receiver,
+        })
+    }
+
+    /// Wait for the next key command
+    pub async fn next(&mut self) -> Option<KeyCommand> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        // Stop the reader before restoring the terminal so later prompts get every key
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = terminal::disable_raw_mode();
+    }
+}
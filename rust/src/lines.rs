@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+use tokio::fs::File;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufWriter};
+
+/// Longest line kept in full; ffmpeg's own lines are far shorter
+const MAX_LINE_BYTES: usize = 4096;
+
+/// Reads lines from a child process without ever holding more than `MAX_LINE_BYTES` of one
+///
+/// Longer lines are cut off at the limit and the rest is skipped, so a process that writes
+/// without newlines can't make the reader grow.
+pub struct BoundedLines<R> {
+    reader: R,
+    line: Vec<u8>,
+}
+
+impl<R: AsyncBufRead + Unpin> BoundedLines<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, line: Vec::new() }
+    }
+
+    /// The next line without its line ending, or `None` at the end of the stream or on a read error
+    pub async fn next_line(&mut self) -> Option<String> {
+        self.line.clear();
+        let mut read_any = false;
+        loop {
+            let buffer = self.reader.fill_buf().await.ok()?;
+            if buffer.is_empty() {
+                return read_any.then(|| self.take_line());
+            }
+            read_any = true;
+
+            let newline = buffer.iter().position(|&byte| byte == b'\n');
+            let chunk = &buffer[..newline.unwrap_or(buffer.len())];
+            let room = MAX_LINE_BYTES.saturating_sub(self.line.len());
+            self.line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+            let used = newline.map_or(buffer.len(), |index| index + 1);
+            self.reader.consume(used);
+            if newline.is_some() {
+                return Some(self.take_line());
+            }
+        }
+    }
+
+    fn take_line(&self) -> String {
+        let line = self.line.strip_suffix(b"\r").unwrap_or(&self.line);
+        String::from_utf8_lossy(line).into_owned()
+    }
+}
+
+/// The last `capacity` lines of a stream, read to the end in constant memory
+///
+/// Older lines are dropped as new ones arrive, so even hours of verbose output only ever
+/// keep `capacity` lines of at most `MAX_LINE_BYTES` each. Every line is also written to
+/// `log`, if given; a failing write stops the log but not the reading.
+pub async fn tail<R: AsyncBufRead + Unpin>(reader: R, capacity: usize, log: Option<File>) -> Vec<String> {
+    let mut tail = VecDeque::with_capacity(capacity);
+    let mut lines = BoundedLines::new(reader);
+    let mut log = log.map(BufWriter::new);
+    while let Some(line) = lines.next_line().await {
+        if let Some(writer) = &mut log {
+            if writer.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                log = None;
+            }
+        }
+        if tail.len() == capacity {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+    if let Some(mut writer) = log {
+        let _ = writer.flush().await;
+    }
+    Vec::from(tail)
+}
@@ -0,0 +1,65 @@
+use crate::naming;
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+
+/// A `<output>.lock` file marking an output as being written by this process
+///
+/// The lock holds our PID and is removed when dropped. Its name is shortened like
+/// `naming::fit_filename` if it would be too long.
+pub struct OutputLock {
+    path: String,
+}
+
+impl OutputLock {
+    /// Take the lock for `output_path`, or return the PID of the live process holding it
+    ///
+    /// Locks left by processes that are no longer running are cleaned up and taken over.
+    pub fn acquire(output_path: &str) -> io::Result<Result<Self, u32>> {
+        let file_name = Path::new(output_path).file_name().unwrap_or_default().to_string_lossy();
+        let path = naming::sibling_path(output_path, &file_name, "lock");
+
+        // A stale lock is removed and the create retried once
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    return Ok(Ok(Self { path }));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let holder = fs::read_to_string(&path).ok().and_then(|pid| pid.trim().parse::<u32>().ok());
+                    match holder {
+                        Some(pid) if is_running(pid) => return Ok(Err(pid)),
+                        _ => {
+                            let _ = fs::remove_file(&path);
+                        }
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(io::Error::new(ErrorKind::AlreadyExists, format!("could not take the lock {}", path)))
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether a process with this PID still exists
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists; kill() has no memory-safety preconditions
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    // EPERM means it exists but belongs to another user
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without signals there is no cheap liveness check, so a lock is assumed to be held
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
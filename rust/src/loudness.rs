@@ -0,0 +1,71 @@
+use crate::ffmpeg;
+use serde::Deserialize;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Loudness level ReplayGain 2.0 normalizes tracks to, in LUFS
+const REPLAYGAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// Loudness measured by ffmpeg's `loudnorm` filter
+#[derive(Debug, Clone, Copy)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS
+    pub integrated_lufs: f64,
+    /// True peak in dBTP
+    pub true_peak_db: f64,
+}
+
+impl Loudness {
+    /// ReplayGain track gain and peak tags for this measurement
+    pub fn replaygain_tags(&self) -> Vec<(String, String)> {
+        let gain = REPLAYGAIN_REFERENCE_LUFS - self.integrated_lufs;
+        let peak = 10f64.powf(self.true_peak_db / 20.0);
+        vec![
+            ("replaygain_track_gain".to_string(), format!("{:+.2} dB", gain)),
+            ("replaygain_track_peak".to_string(), format!("{:.6}", peak)),
+        ]
+    }
+}
+
+/// The part of loudnorm's JSON report we use (values are strings)
+#[derive(Deserialize)]
+struct RawLoudnorm {
+    input_i: String,
+    input_tp: String,
+}
+
+/// Measure an input's loudness with a decode-only loudnorm pass, or `None` if it fails
+pub async fn measure(input_path: &str, headers: &[String]) -> Option<Loudness> {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-nostdin", "-hide_banner"]);
+    if !headers.is_empty() {
+        let headers: String = headers.iter().map(|h| format!("{}\r\n", h)).collect();
+        command.args(["-headers", &headers]);
+    }
+    command.args(ffmpeg::input_args_for(input_path));
+
+    let output = command
+        .args(["-i", input_path, "-vn", "-af", "loudnorm=print_format=json", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // The report is the last JSON object on stderr
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let start = stderr.rfind('{')?;
+    let end = stderr.rfind('}')?;
+    let raw: RawLoudnorm = serde_json::from_str(stderr.get(start..=end)?).ok()?;
+
+    let integrated_lufs: f64 = raw.input_i.trim().parse().ok()?;
+    let true_peak_db: f64 = raw.input_tp.trim().parse().ok()?;
+    // Silence measures as -inf / -70 LUFS, which would give an absurd gain
+    if !integrated_lufs.is_finite() || !true_peak_db.is_finite() || integrated_lufs <= -70.0 {
+        return None;
+    }
+
+    Some(Loudness { integrated_lufs, true_peak_db })
+}
@@ -1,23 +1,101 @@
+mod archive;
+mod benchmark;
+mod cancel;
+mod cli;
+mod compare;
 mod config;
 mod converter;
+mod data_uri;
+mod error;
+mod estimate;
+mod ffmpeg;
+mod fingerprint;
+mod format;
+mod formats;
+mod history;
+mod hooks;
+mod keys;
+mod lines;
+mod lock;
+mod loudness;
+mod naming;
+mod notify;
+mod oneline;
+mod options;
+mod paths;
+mod pattern;
+mod preset;
+mod progress;
+mod recovery;
+mod remote;
+mod segments;
+mod serve;
+mod sniff;
+mod symbols;
+mod table;
+mod tempdir;
+mod template;
+mod probe;
 mod video_file;
 
+use clap::{CommandFactory, Parser};
+use cli::{Args, CliCommand};
 use colored::*;
 use converter::VideoConverter;
+use error::ConverterError;
+use symbols::Status;
+use std::time::Duration;
+
+/// How long a cancelled conversion gets to stop ffmpeg and clean up before the process exits anyway
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() {
-    // Set up ctrl+c handler
-    tokio::spawn(async {
-        tokio::signal::ctrl_c().await.ok();
-        println!("\n{}", "👋 Goodbye!".blue());
-        std::process::exit(0);
-    });
-
-    let converter = VideoConverter::new();
-
-    if let Err(e) = converter.run().await {
-        println!("{}", format!("❌ Fatal error: {}", e).red());
-        std::process::exit(1);
+    let args = Args::parse();
+
+    if let Some(CliCommand::Completions { shell }) = args.command {
+        clap_complete::generate(shell, &mut Args::command(), "convert", &mut std::io::stdout());
+        return;
+    }
+
+    // Temp files are removed however the run ends, including on a panic
+    let _cleanup = tempdir::Cleanup;
+
+    let serving = args.serve.is_some();
+    let converter = match VideoConverter::new(args) {
+        Ok(converter) => converter,
+        Err(e) => {
+            println!("{}", symbols::line(Status::Failure, e).red());
+            tempdir::exit(1);
+        }
+    };
+
+    // Ctrl+C cancels the conversion in progress, which cleans up after itself and lets the
+    // run return. Whatever else is going on, the process exits after `CANCEL_GRACE` or on a
+    // second Ctrl+C. The server shuts down on its own so it can remove its socket.
+    if !serving {
+        let cancel = converter.cancel_token();
+        tokio::spawn(async move {
+            tokio::signal::ctrl_c().await.ok();
+            cancel.cancel();
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = tokio::time::sleep(CANCEL_GRACE) => {}
+            }
+            println!("\n{}", "👋 Goodbye!".blue());
+            tempdir::exit(0);
+        });
+    }
+
+    match converter.run().await {
+        Ok(()) => {}
+        Err(e) if matches!(e.downcast_ref::<ConverterError>(), Some(ConverterError::Cancelled)) => {
+            println!("{}", "👋 Goodbye!".blue());
+            tempdir::exit(0);
+        }
+        Err(e) => {
+            println!("{}", symbols::line(Status::Failure, format!("Fatal error: {}", e)).red());
+            tempdir::exit(1);
+        }
     }
 }
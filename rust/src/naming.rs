@@ -0,0 +1,155 @@
+use std::path::Path;
+
+/// Characters that are illegal in file names on at least one common filesystem
+const ILLEGAL_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Check whether a character can't be used in a file name
+pub fn is_illegal_char(c: char) -> bool {
+    ILLEGAL_CHARS.contains(&c) || c.is_control()
+}
+
+/// Make a file name stem safe to use on any filesystem
+///
+/// Illegal characters become `replacement`, trailing dots/spaces are trimmed
+/// (Windows drops them silently), and reserved device names get `replacement`
+/// appended so `CON` becomes `CON_`.
+pub fn sanitize_filename(name: &str, replacement: char) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| if is_illegal_char(c) { replacement } else { c })
+        .collect();
+
+    let mut sanitized = replaced.trim().trim_end_matches(['.', ' ']).to_string();
+
+    if sanitized.is_empty() {
+        return "output".to_string();
+    }
+
+    // Windows treats "NUL.txt" like "NUL", so compare the part before the first dot
+    let base = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(base)) {
+        sanitized.push(replacement);
+    }
+
+    sanitized
+}
+
+/// Longest file name, in bytes, that common filesystems accept (ext4, APFS, NTFS)
+pub const MAX_FILENAME_BYTES: usize = 255;
+
+/// Suffixes added to an output's name for the files written beside it: its lock, the
+/// `<stem>.part.<ext>` it's written to before the rename, and the kept ffmpeg log
+const SIDECAR_SUFFIXES: &[&str] = &[".lock", ".part", ".log"];
+
+/// Room left in fitted output names for the longest sidecar suffix, so those files can
+/// always be created too
+pub const SIDECAR_SUFFIX_BYTES: usize = longest(SIDECAR_SUFFIXES);
+
+const fn longest(suffixes: &[&str]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < suffixes.len() {
+        if suffixes[i].len() > max {
+            max = suffixes[i].len();
+        }
+        i += 1;
+    }
+    max
+}
+
+/// Build `<stem>.<extension>`, shortening the stem if the name would be too long
+///
+/// A truncated stem gets a short hash of the full stem appended, so long names that
+/// share a prefix still map to different files. Returns the name and whether it
+/// had to be truncated.
+pub fn fit_filename(stem: &str, extension: &str) -> (String, bool) {
+    fit_within(stem, extension, MAX_FILENAME_BYTES - SIDECAR_SUFFIX_BYTES)
+}
+
+/// Path of a file beside `path` named `<stem>.<extension>`, shortened like `fit_filename`
+/// if it wouldn't fit the filesystem (only possible when `path` wasn't fitted itself)
+pub fn sibling_path(path: &str, stem: &str, extension: &str) -> String {
+    let (file_name, _) = fit_within(stem, extension, MAX_FILENAME_BYTES);
+    Path::new(path).with_file_name(file_name).to_string_lossy().to_string()
+}
+
+fn fit_within(stem: &str, extension: &str, limit: usize) -> (String, bool) {
+    let filename = format!("{}.{}", stem, extension);
+    if filename.len() <= limit {
+        return (filename, false);
+    }
+
+    let suffix = format!("~{:08x}", fnv1a(stem));
+    let budget = limit - extension.len() - 1 - suffix.len();
+
+    // Cut on a character boundary so multi-byte names stay valid UTF-8
+    let mut end = budget.min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    (format!("{}{}.{}", stem[..end].trim_end(), suffix, extension), true)
+}
+
+/// 32-bit FNV-1a hash, stable across runs and platforms
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_slashes() {
+        assert_eq!(sanitize_filename("AC/DC - Live", '_'), "AC_DC - Live");
+        assert_eq!(sanitize_filename(r"left\right", '_'), "left_right");
+    }
+
+    #[test]
+    fn replaces_colons() {
+        assert_eq!(sanitize_filename("Chapter 1: Intro", '_'), "Chapter 1_ Intro");
+        assert_eq!(sanitize_filename("12:30", '-'), "12-30");
+    }
+
+    #[test]
+    fn trims_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_filename("The End...", '_'), "The End");
+        assert_eq!(sanitize_filename("  padded . ", '_'), "padded");
+        assert_eq!(sanitize_filename(" ... ", '_'), "output");
+    }
+
+    #[test]
+    fn suffixes_windows_reserved_names() {
+        assert_eq!(sanitize_filename("CON", '_'), "CON_");
+        assert_eq!(sanitize_filename("nul", '_'), "nul_");
+        assert_eq!(sanitize_filename("COM1", '_'), "COM1_");
+        assert_eq!(sanitize_filename("NUL.backup", '_'), "NUL.backup_");
+        assert_eq!(sanitize_filename("CONSOLE", '_'), "CONSOLE");
+    }
+
+    #[test]
+    fn long_names_leave_room_for_sidecar_files() {
+        let (name, truncated) = fit_filename(&"a".repeat(300), "mp3");
+        assert!(truncated);
+        assert!(name.len() + SIDECAR_SUFFIX_BYTES <= MAX_FILENAME_BYTES);
+
+        let lock = sibling_path(&format!("/music/{}", name), &name, "lock");
+        assert_eq!(lock, format!("/music/{}.lock", name));
+    }
+
+    #[test]
+    fn sibling_names_that_dont_fit_are_shortened() {
+        let stem = "b".repeat(260);
+        let sibling = sibling_path("/music/out.mp3", &stem, "log");
+        let name = sibling.strip_prefix("/music/").unwrap();
+        assert!(name.len() <= MAX_FILENAME_BYTES && name.ends_with(".log"));
+    }
+}
@@ -0,0 +1,33 @@
+use notify_rust::Notification;
+use std::time::Duration;
+
+/// Post a desktop notification, returning whether it could be shown
+///
+/// Headless systems with no notification service just get `false`.
+pub async fn send(title: &str, body: &str) -> bool {
+    let (title, body) = (title.to_string(), body.to_string());
+    // Showing a notification blocks on the desktop's notification service
+    let shown = tokio::task::spawn_blocking(move || {
+        Notification::new()
+            .appname("convert")
+            .summary(&title)
+            .body(&body)
+            .show()
+            .is_ok()
+    })
+    .await;
+    matches!(shown, Ok(true))
+}
+
+/// Elapsed time as `1h 02m 03s`, `2m 03s` or `3s`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let total = elapsed.as_secs();
+    let (hours, minutes, seconds) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
@@ -0,0 +1,57 @@
+use crate::progress;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// `--oneline` output: one tab-separated line per conversion and nothing else on stdout
+///
+/// Everything the converter normally prints is sent to the null device instead, colors
+/// are turned off and spinners are hidden. Warnings on stderr are left alone.
+pub struct OnelineReport {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl OnelineReport {
+    /// Silence the regular output, keeping a handle on the real stdout for the result lines
+    pub fn start() -> io::Result<Self> {
+        colored::control::set_override(false);
+        progress::hide_spinners();
+        Ok(Self { out: Mutex::new(redirect_stdout()?) })
+    }
+
+    /// Write `input  output  status  size_mb  seconds` for one conversion
+    pub fn record(&self, input: &str, output: &str, status: &str, size_mb: Option<f64>, seconds: f64) {
+        let size = size_mb.map(|mb| format!("{:.2}", mb)).unwrap_or_else(|| "-".to_string());
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(out, "{}\t{}\t{}\t{}\t{:.1}", input, output, status, size, seconds);
+        let _ = out.flush();
+    }
+}
+
+/// Point stdout at /dev/null and return a writer for the original stdout
+#[cfg(unix)]
+fn redirect_stdout() -> io::Result<Box<dyn Write + Send>> {
+    use std::fs::File;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    io::stdout().flush()?;
+    // SAFETY: dup() only duplicates our own stdout descriptor
+    let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if saved < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `saved` is a fresh descriptor that nothing else owns
+    let real_stdout = unsafe { File::from_raw_fd(saved) };
+
+    let null = File::options().write(true).open("/dev/null")?;
+    // SAFETY: both descriptors are open; dup2() repoints stdout at the null device
+    if unsafe { libc::dup2(null.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Box::new(real_stdout))
+}
+
+/// Without descriptor juggling the regular output can't be silenced, so it stays on stdout
+#[cfg(not(unix))]
+fn redirect_stdout() -> io::Result<Box<dyn Write + Send>> {
+    Ok(Box::new(io::stdout()))
+}
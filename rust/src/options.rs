@@ -0,0 +1,287 @@
+use crate::format::{AudioFormat, Container, StreamCopy};
+use clap::ValueEnum;
+
+/// Bitrates offered in the interactive prompt (kbps)
+pub const BITRATES: [u32; 6] = [96, 128, 160, 192, 256, 320];
+
+/// Bitrate used when none is chosen (kbps)
+pub const DEFAULT_BITRATE: u32 = 192;
+
+/// EBU R128 loudness normalization, targeting the common podcast level of -16 LUFS
+const LOUDNORM_FILTER: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
+
+/// Equal-power sum of the front pair into one channel, avoiding the thin sound of a plain `-ac 1`
+const MONO_DOWNMIX_FILTER: &str = "pan=mono|c0=0.5*c0+0.5*c1";
+
+/// Surround channels folded into each side at -3 dB
+const SURROUND_WEIGHT: &str = "0.707";
+
+/// Stretch or pad audio to its timestamps so gaps and drift from VFR sources don't desync it
+const RESYNC_FILTER: &str = "aresample=async=1";
+
+/// Trim leading silence and shorten pauses longer than a second
+const SILENCEREMOVE_FILTER: &str =
+    "silenceremove=start_periods=1:start_threshold=-50dB:stop_periods=-1:stop_duration=1:stop_threshold=-50dB";
+
+/// Sample rate converters ffmpeg can use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Resampler {
+    /// ffmpeg's built-in resampler (fast, good)
+    #[default]
+    Swr,
+    /// The SoX resampler (slower, higher quality), if ffmpeg was built with libsoxr
+    Soxr,
+}
+
+/// What to do when the source audio already is what the conversion would produce
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmartCopy {
+    /// Always encode
+    #[default]
+    Off,
+    /// Ask whether to copy instead (interactive runs)
+    Ask,
+    /// Copy without asking (`--smart-copy`)
+    Auto,
+}
+
+/// Audio settings applied to a conversion
+#[derive(Debug, Clone)]
+pub struct ConversionOptions {
+    pub format: AudioFormat,
+    /// ffmpeg encoder name (e.g. `libmp3lame`, `aac`, `libfdk_aac`)
+    pub encoder: String,
+    /// Container chosen with `--container` (`None` uses the format's usual one)
+    pub container: Option<Container>,
+    /// Copy the source audio stream instead of encoding it (see `--format-from-source`)
+    pub copy_audio: bool,
+    /// Where the copied stream goes, once the source codec is known
+    pub stream_copy: Option<StreamCopy>,
+    /// Whether to copy sources that already match the format and bitrate
+    pub smart_copy: SmartCopy,
+    pub bitrate_kbps: u32,
+    /// Output channel count (`None` keeps the source layout)
+    pub channels: Option<u32>,
+    /// Mix down to mono with a pan filter (takes precedence over `channels`)
+    pub downmix_mono: bool,
+    /// Output sample rate in Hz (`None` keeps the source rate)
+    pub sample_rate: Option<u32>,
+    /// Resampler for converting to `sample_rate`
+    pub resampler: Resampler,
+    /// soxr precision in bits (`None` keeps ffmpeg's default)
+    pub resampler_precision: Option<u32>,
+    /// Fold surround sources down to stereo with this pan filter before anything else
+    pub surround_filter: Option<String>,
+    /// Resample against timestamps to keep the audio continuous (variable-frame-rate sources)
+    pub resync_audio: bool,
+    /// Apply loudness normalization
+    pub normalize: bool,
+    /// Strip silent stretches
+    pub remove_silence: bool,
+    /// Further formats written from the same decode, next to the main output
+    pub extra_formats: Vec<AudioFormat>,
+    /// Split the output into parts of this many seconds
+    pub segment_seconds: Option<u32>,
+    /// Only convert this many seconds from the start (`-t` after the input)
+    pub preview_seconds: Option<u32>,
+    /// Stop after this many seconds of a live stream (`-t` after the input)
+    pub record_seconds: Option<f64>,
+    /// Seek after decoding (`-ss` after `-i`) for sample-accurate trims instead of the fast keyframe seek
+    pub accurate_seek: bool,
+    /// Play the input this many times in a row (`-stream_loop`); `None` plays it once
+    pub loop_count: Option<u32>,
+    /// Conservative MP3 settings for picky hardware players (see `--compat`)
+    pub compat: bool,
+    /// Keep parts free of gaps and clicks when played back to back (see `--gapless`)
+    pub gapless: bool,
+    /// Hardware decoding method passed to `-hwaccel`
+    pub hwaccel: Option<String>,
+    /// AES-128 key for encrypted MP4/DASH inputs, as hex (`-decryption_key`)
+    pub decryption_key: Option<String>,
+    /// Conservative decode for damaged inputs (see `--safe-mode`)
+    pub safe_mode: bool,
+    /// Thread limit for ffmpeg's decoder and encoder (`None` lets ffmpeg decide)
+    pub threads: Option<u32>,
+    /// Explicit metadata tags (`title`, `artist`, ...) written over any copied from the source
+    pub tags: Vec<(String, String)>,
+    /// Leave out every tag and chapter of the source (see `--strip-metadata`)
+    pub strip_metadata: bool,
+    /// Byte-identical output for identical input and settings (see `--reproducible`)
+    pub reproducible: bool,
+    /// File that receives ffmpeg's full stderr (see `--keep-logs`)
+    pub log_path: Option<String>,
+    /// Extra ffmpeg flags placed before the input
+    pub input_args: Vec<String>,
+    /// Extra ffmpeg flags placed before the output
+    pub output_args: Vec<String>,
+}
+
+impl ConversionOptions {
+    pub fn new(format: AudioFormat, bitrate_kbps: u32) -> Self {
+        Self {
+            format,
+            encoder: format.default_encoder().to_string(),
+            container: None,
+            copy_audio: false,
+            stream_copy: None,
+            smart_copy: SmartCopy::Off,
+            bitrate_kbps,
+            channels: None,
+            downmix_mono: false,
+            sample_rate: None,
+            resampler: Resampler::Swr,
+            resampler_precision: None,
+            surround_filter: None,
+            resync_audio: false,
+            normalize: false,
+            remove_silence: false,
+            extra_formats: Vec::new(),
+            segment_seconds: None,
+            preview_seconds: None,
+            record_seconds: None,
+            accurate_seek: false,
+            loop_count: None,
+            gapless: false,
+            compat: false,
+            hwaccel: None,
+            decryption_key: None,
+            safe_mode: false,
+            threads: None,
+            tags: Vec::new(),
+            strip_metadata: false,
+            reproducible: false,
+            log_path: None,
+            input_args: Vec::new(),
+            output_args: Vec::new(),
+        }
+    }
+
+    /// Switch to another format, resetting the encoder to match
+    ///
+    /// A chosen container is kept only if it can hold the new format. Any stream copy
+    /// ends, since the audio is now encoded.
+    pub fn set_format(&mut self, format: AudioFormat) {
+        self.format = format;
+        self.copy_audio = false;
+        self.stream_copy = None;
+        self.encoder = format.default_encoder().to_string();
+        if !self.container.is_some_and(|container| container.supports(format)) {
+            self.container = None;
+        }
+    }
+
+    /// File extension for the output
+    pub fn extension(&self) -> &'static str {
+        if let Some(copy) = &self.stream_copy {
+            return copy.extension;
+        }
+        match self.container {
+            Some(container) => container.extension(),
+            None => self.format.extension(),
+        }
+    }
+
+    /// ffmpeg muxer for the output
+    pub fn muxer(&self) -> &'static str {
+        if let Some(copy) = &self.stream_copy {
+            return copy.muxer;
+        }
+        match self.container {
+            Some(container) => container.muxer(),
+            None => self.format.muxer(),
+        }
+    }
+
+    /// Muxer arguments for the output
+    pub fn container_args(&self) -> &'static [&'static str] {
+        if let Some(copy) = &self.stream_copy {
+            return copy.container_args;
+        }
+        match self.container {
+            Some(container) => container.container_args(),
+            None => self.format.container_args(),
+        }
+    }
+
+    /// ffmpeg audio filters these options require, in the order they should run
+    pub fn audio_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        // Timestamps have to be fixed before anything else looks at the samples
+        if self.resync_audio {
+            filters.push(RESYNC_FILTER.to_string());
+        }
+        if let Some(surround) = &self.surround_filter {
+            filters.push(surround.clone());
+        }
+        if self.downmix_mono {
+            filters.push(MONO_DOWNMIX_FILTER.to_string());
+        }
+        if self.remove_silence {
+            filters.push(SILENCEREMOVE_FILTER.to_string());
+        }
+        if self.normalize {
+            filters.push(LOUDNORM_FILTER.to_string());
+        }
+        // Last, so it also brings loudnorm's 192 kHz output down to the target rate
+        if let (Resampler::Soxr, Some(rate)) = (self.resampler, self.sample_rate) {
+            let mut filter = format!("aresample={}:resampler=soxr", rate);
+            if let Some(precision) = self.resampler_precision {
+                filter.push_str(&format!(":precision={}", precision));
+            }
+            filters.push(filter);
+        }
+        filters
+    }
+
+    /// Channel count to request with `-ac`, if any
+    pub fn channel_count(&self) -> Option<u32> {
+        // The pan filter already produces mono; a conflicting -ac would remix it
+        if self.downmix_mono {
+            None
+        } else {
+            self.channels
+        }
+    }
+}
+
+/// Stereo downmix for a surround layout that keeps the centre (dialogue) channel at full level
+///
+/// Returns `None` for layouts we don't know the channel names of.
+pub fn stereo_downmix_filter(layout: &str) -> Option<String> {
+    let channels: &[&str] = match layout {
+        "quad" => &["FL", "FR", "BL", "BR"],
+        "5.0" => &["FL", "FR", "FC", "BL", "BR"],
+        "5.0(side)" => &["FL", "FR", "FC", "SL", "SR"],
+        "5.1" => &["FL", "FR", "FC", "LFE", "BL", "BR"],
+        "5.1(side)" => &["FL", "FR", "FC", "LFE", "SL", "SR"],
+        "6.1" => &["FL", "FR", "FC", "LFE", "BC", "SL", "SR"],
+        "7.1" => &["FL", "FR", "FC", "LFE", "BL", "BR", "SL", "SR"],
+        _ => return None,
+    };
+
+    // LFE is left out; `<` rescales the gains so the sum can't clip
+    let side = |front: &str, back: &str, surround: &str| {
+        let mut terms = vec![front.to_string()];
+        for &channel in channels {
+            match channel {
+                "FC" => terms.push("FC".to_string()),
+                "BC" => terms.push("0.5*BC".to_string()),
+                c if c == back || c == surround => terms.push(format!("{}*{}", SURROUND_WEIGHT, c)),
+                _ => {}
+            }
+        }
+        terms.join("+")
+    };
+
+    Some(format!(
+        "pan=stereo|FL<{}|FR<{}",
+        side("FL", "BL", "SL"),
+        side("FR", "BR", "SR")
+    ))
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        Self::new(AudioFormat::default(), DEFAULT_BITRATE)
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+/// Expand a leading `~` to the current user's home directory
+///
+/// Anything else, including paths where the home directory can't be found, is returned unchanged.
+pub fn expand_tilde(path: &str) -> String {
+    shellexpand::tilde(path).into_owned()
+}
+
+/// Folder for state kept between runs, such as the recent-files history
+///
+/// `$XDG_STATE_HOME/video-to-audio` on Linux (by default `~/.local/state/video-to-audio`),
+/// otherwise the local data folder (`%LOCALAPPDATA%` on Windows).
+pub fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|base| base.join("video-to-audio"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs::home_dir().expect("tests need a home directory").to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn expands_bare_tilde() {
+        assert_eq!(expand_tilde("~"), home());
+    }
+
+    #[test]
+    fn expands_tilde_with_subfolder() {
+        assert_eq!(expand_tilde("~/Videos/talk.mp4"), format!("{}/Videos/talk.mp4", home()));
+    }
+
+    #[test]
+    fn leaves_absolute_paths_unchanged() {
+        assert_eq!(expand_tilde("/srv/videos/talk.mp4"), "/srv/videos/talk.mp4");
+        assert_eq!(expand_tilde(r"C:\Videos\talk.mp4"), r"C:\Videos\talk.mp4");
+    }
+
+    #[test]
+    fn leaves_tilde_in_the_middle_unchanged() {
+        assert_eq!(expand_tilde("videos/~/talk.mp4"), "videos/~/talk.mp4");
+        assert_eq!(expand_tilde("/tmp/~backup"), "/tmp/~backup");
+    }
+
+    #[test]
+    fn leaves_unknown_users_unchanged() {
+        assert_eq!(expand_tilde("~no-such-user-here/talk.mp4"), "~no-such-user-here/talk.mp4");
+    }
+}
@@ -0,0 +1,56 @@
+/// Match text against a shell-style glob (`*` any run of characters, `?` any one character)
+///
+/// Matching ignores case, so `lecture*` also picks up `Lecture 01.mp4`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was, and how much text it has swallowed so far
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Check a file against include/exclude globs; excludes win, and no includes means everything
+///
+/// Patterns containing a `/` are matched against the path relative to the scan root,
+/// the rest against the bare file name.
+pub fn is_selected(relative_path: &str, includes: &[String], excludes: &[String]) -> bool {
+    let relative_path = relative_path.replace('\\', "/");
+    let file_name = relative_path.rsplit('/').next().unwrap_or(&relative_path);
+    let matches = |pattern: &String| {
+        if pattern.contains('/') {
+            glob_match(pattern, &relative_path)
+        } else {
+            glob_match(pattern, file_name)
+        }
+    };
+
+    if excludes.iter().any(matches) {
+        return false;
+    }
+    includes.is_empty() || includes.iter().any(matches)
+}
@@ -0,0 +1,54 @@
+use crate::format::AudioFormat;
+use crate::options::ConversionOptions;
+use clap::ValueEnum;
+
+/// Named bundles of conversion settings for common use cases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Speech: 64 kbps mono at 22.05 kHz with silence removed
+    Voice,
+    /// Music: 256 kbps stereo at 44.1 kHz
+    Music,
+    /// Podcasts: 96 kbps mono at 44.1 kHz, loudness-normalized
+    Podcast,
+    /// Old car stereos and other picky players: 192 kbps CBR MP3, plain stereo at 44.1 kHz, no Xing header
+    Compat,
+    /// Machine learning input: 16 kHz mono 16-bit PCM WAV
+    Ml,
+}
+
+impl Preset {
+    /// Fully-populated options for this preset
+    pub fn options(&self) -> ConversionOptions {
+        match self {
+            Preset::Voice => ConversionOptions {
+                channels: Some(1),
+                sample_rate: Some(22050),
+                remove_silence: true,
+                ..ConversionOptions::new(AudioFormat::Mp3, 64)
+            },
+            Preset::Music => ConversionOptions {
+                channels: Some(2),
+                sample_rate: Some(44100),
+                ..ConversionOptions::new(AudioFormat::Mp3, 256)
+            },
+            Preset::Podcast => ConversionOptions {
+                channels: Some(1),
+                sample_rate: Some(44100),
+                normalize: true,
+                ..ConversionOptions::new(AudioFormat::Mp3, 96)
+            },
+            Preset::Compat => ConversionOptions {
+                channels: Some(2),
+                sample_rate: Some(44100),
+                compat: true,
+                ..ConversionOptions::new(AudioFormat::Mp3, 192)
+            },
+            Preset::Ml => ConversionOptions {
+                channels: Some(1),
+                sample_rate: Some(16000),
+                ..ConversionOptions::new(AudioFormat::Wav, 0)
+            },
+        }
+    }
+}
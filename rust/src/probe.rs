@@ -0,0 +1,377 @@
+use crate::error::ConverterError;
+use crate::ffmpeg;
+use colored::*;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tokio::process::Command;
+use url::Url;
+
+/// How often a network input is probed before giving up
+const PROBE_ATTEMPTS: u32 = 3;
+
+/// Wait before the first probe retry; it doubles with every further one
+const PROBE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A chapter marker read from the source file
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// An audio stream in the source
+#[derive(Debug, Clone)]
+pub struct AudioStream {
+    /// Codec name such as `aac` or `opus`
+    pub codec: Option<String>,
+    pub channels: u32,
+    /// ffmpeg layout name such as `stereo`, `5.1` or `5.1(side)`
+    pub channel_layout: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bitrate_kbps: Option<u32>,
+    /// Language tag such as `eng`
+    pub language: Option<String>,
+}
+
+/// Media information gathered with ffprobe
+#[derive(Debug, Clone, Default)]
+pub struct ProbeInfo {
+    pub chapters: Vec<Chapter>,
+    /// Audio streams in file order
+    pub audio_streams: Vec<AudioStream>,
+    /// Total duration in seconds, when ffprobe could tell
+    pub duration: Option<f64>,
+    /// Overall bitrate of the file in kbps
+    pub bitrate_kbps: Option<u32>,
+    /// Demuxer ffprobe picked, e.g. `mov` or `matroska`
+    pub demuxer: Option<String>,
+    /// The video has a variable frame rate (typical of screen recordings)
+    pub variable_frame_rate: bool,
+}
+
+impl ProbeInfo {
+    /// The first audio stream, which is the one ffmpeg converts by default
+    pub fn audio(&self) -> Option<&AudioStream> {
+        self.audio_streams.first()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawProbe {
+    #[serde(default)]
+    chapters: Vec<RawChapter>,
+    format: Option<RawFormat>,
+    #[serde(default)]
+    streams: Vec<RawStream>,
+}
+
+#[derive(Deserialize)]
+struct RawStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    sample_rate: Option<String>,
+    bit_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    avg_frame_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct RawFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawChapter {
+    start_time: String,
+    end_time: String,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+/// Remembers probe results for the rest of the run, keyed by path and modification time
+///
+/// Several features need the same probe data, so each input is probed at most once
+/// unless the file changes on disk in the meantime.
+#[derive(Default)]
+pub struct ProbeCache {
+    entries: Mutex<HashMap<(String, Option<SystemTime>), ProbeInfo>>,
+}
+
+impl ProbeCache {
+    /// Probe an input, reusing an earlier result if the file hasn't changed since
+    ///
+    /// `headers` are sent along when the input is a URL.
+    pub async fn probe(&self, input_path: &str, headers: &[String]) -> Result<ProbeInfo, ConverterError> {
+        // URLs have no mtime, so they're cached by address alone
+        let modified = fs::metadata(input_path).and_then(|m| m.modified()).ok();
+        let key = (input_path.to_string(), modified);
+
+        if let Some(info) = self.entries.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(info.clone());
+        }
+
+        let info = probe(input_path, headers).await?;
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, info.clone());
+        Ok(info)
+    }
+}
+
+/// Probe an input with ffprobe and parse the parts we care about
+///
+/// Network inputs get a few attempts with growing pauses in between, since a dropped
+/// connection or a slow server shouldn't take every probe-based feature down with it.
+/// Answers that won't change on a retry (a missing ffprobe, HTTP 4xx) fail at once.
+async fn probe(input_path: &str, headers: &[String]) -> Result<ProbeInfo, ConverterError> {
+    let attempts = if is_network_input(input_path) { PROBE_ATTEMPTS } else { 1 };
+    let mut delay = PROBE_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match run_ffprobe(input_path, headers).await {
+            Err(ConverterError::ProbeFailed(reason)) if attempt < attempts && is_transient(&reason) => {
+                // On stderr so `--probe --json` output stays parseable
+                eprintln!("{}", format!(
+                    "🔁 ffprobe failed ({}), retrying in {}s ({}/{})",
+                    reason.lines().next().unwrap_or_default(),
+                    delay.as_secs(),
+                    attempt + 1,
+                    attempts
+                ).yellow());
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether an input is read over the network (HTTP, RTMP, ...) rather than from disk
+fn is_network_input(input_path: &str) -> bool {
+    // Windows drive letters parse as one-letter schemes
+    Url::parse(input_path).is_ok_and(|url| url.scheme() != "file" && url.scheme().len() > 1)
+}
+
+/// Whether a probe failure might go away when tried again
+fn is_transient(reason: &str) -> bool {
+    let permanent = ["ffprobe not found", "Server returned 4", "No such file"];
+    !permanent.iter().any(|marker| reason.contains(marker))
+}
+
+/// Run ffprobe once and parse its JSON
+async fn run_ffprobe(input_path: &str, headers: &[String]) -> Result<ProbeInfo, ConverterError> {
+    let mut command = Command::new("ffprobe");
+    command.args([
+        "-v", "error",
+        "-print_format", "json",
+        "-show_chapters",
+        "-show_format",
+        "-show_streams",
+    ]);
+    if !headers.is_empty() {
+        let headers: String = headers.iter().map(|h| format!("{}\r\n", h)).collect();
+        command.args(["-headers", &headers]);
+    }
+    command.args(ffmpeg::input_args_for(input_path));
+
+    let output = command
+        .arg(input_path)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => ConverterError::ProbeFailed("ffprobe not found".to_string()),
+            _ => ConverterError::Io(e),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ConverterError::ProbeFailed(match stderr.trim() {
+            "" => format!("ffprobe stopped with {}", output.status),
+            stderr => stderr.to_string(),
+        }));
+    }
+
+    if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Err(ConverterError::ProbeFailed("ffprobe printed nothing".to_string()));
+    }
+    let raw: RawProbe = serde_json::from_slice(&output.stdout).map_err(|e| {
+        let problem = if e.is_eof() { "cut off" } else { "not valid JSON" };
+        ConverterError::ProbeFailed(format!("ffprobe output is {} (line {}, column {}): {}", problem, e.line(), e.column(), e))
+    })?;
+
+    let chapters = raw
+        .chapters
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, chapter)| {
+            let start = chapter.start_time.parse().ok()?;
+            let end = chapter.end_time.parse().ok()?;
+            let title = chapter
+                .tags
+                .get("title")
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", index + 1));
+            Some(Chapter { title, start, end })
+        })
+        .collect();
+
+    let variable_frame_rate = raw
+        .streams
+        .iter()
+        .find(|stream| stream.codec_type.as_deref() == Some("video"))
+        .is_some_and(is_variable_frame_rate);
+
+    let audio_streams = raw
+        .streams
+        .into_iter()
+        .filter(|stream| stream.codec_type.as_deref() == Some("audio"))
+        .filter_map(|stream| {
+            Some(AudioStream {
+                codec: stream.codec_name,
+                channels: stream.channels?,
+                channel_layout: stream.channel_layout.filter(|l| !l.is_empty() && l != "unknown"),
+                sample_rate: stream.sample_rate.and_then(|r| r.parse().ok()),
+                bitrate_kbps: stream.bit_rate.as_deref().and_then(bits_to_kbps),
+                language: stream.tags.get("language").cloned().filter(|l| l != "und"),
+            })
+        })
+        .collect();
+
+    let format = raw.format;
+    let duration = format
+        .as_ref()
+        .and_then(|f| f.duration.as_deref())
+        .and_then(|d| d.parse().ok())
+        .filter(|d: &f64| *d > 0.0);
+    let bitrate_kbps = format.as_ref().and_then(|f| f.bit_rate.as_deref()).and_then(bits_to_kbps);
+    // format_name lists every alias ("mov,mp4,m4a,..."); the first one names the demuxer
+    let demuxer = format
+        .as_ref()
+        .and_then(|f| f.format_name.as_deref())
+        .and_then(|names| names.split(',').next())
+        .map(str::to_string);
+
+    Ok(ProbeInfo { chapters, audio_streams, duration, bitrate_kbps, demuxer, variable_frame_rate })
+}
+
+/// Whether a video stream's average frame rate strays from its nominal one by more than 1%
+///
+/// Cover art and other still images report no average rate and never count.
+fn is_variable_frame_rate(stream: &RawStream) -> bool {
+    let rate = |raw: Option<&str>| {
+        let (num, den) = raw?.split_once('/')?;
+        let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+        (num > 0.0 && den > 0.0).then(|| num / den)
+    };
+    match (rate(stream.r_frame_rate.as_deref()), rate(stream.avg_frame_rate.as_deref())) {
+        (Some(nominal), Some(average)) => (nominal - average).abs() / nominal > 0.01,
+        _ => false,
+    }
+}
+
+/// Convert ffprobe's bits-per-second string to whole kbps
+fn bits_to_kbps(bits: &str) -> Option<u32> {
+    bits.parse::<u64>().ok().map(|b| (b / 1000) as u32)
+}
+
+/// Print a human-readable summary of a probed input's audio
+pub fn print_summary(input_path: &str, info: &ProbeInfo) {
+    println!("{}", format!("📄 {}", input_path).cyan());
+    match info.duration {
+        Some(duration) => println!("{}", format!("⏱️  Duration: {}", format_timestamp(duration)).blue()),
+        None => println!("{}", "⏱️  Duration: unknown".blue()),
+    }
+    if let Some(bitrate) = info.bitrate_kbps {
+        println!("{}", format!("📦 Overall bitrate: {} kbps", bitrate).blue());
+    }
+
+    if info.audio_streams.is_empty() {
+        println!("{}", "⚠️  No audio streams".yellow());
+    }
+    for (index, stream) in info.audio_streams.iter().enumerate() {
+        let mut details = vec![stream.codec.clone().unwrap_or_else(|| "unknown codec".to_string())];
+        if let Some(bitrate) = stream.bitrate_kbps {
+            details.push(format!("{} kbps", bitrate));
+        }
+        if let Some(sample_rate) = stream.sample_rate {
+            details.push(format!("{} Hz", sample_rate));
+        }
+        details.push(match &stream.channel_layout {
+            Some(layout) => format!("{} channel(s) ({})", stream.channels, layout),
+            None => format!("{} channel(s)", stream.channels),
+        });
+        if let Some(language) = &stream.language {
+            details.push(format!("language: {}", language));
+        }
+        println!("{}", format!("🎵 Audio stream {}: {}", index + 1, details.join(", ")).green());
+    }
+
+    println!("{}", format!("📑 Chapters: {}", info.chapters.len()).blue());
+    if info.variable_frame_rate {
+        println!("{}", "🎞️  Variable frame rate video (audio is resampled against its timestamps when converting)".yellow());
+    }
+}
+
+/// One-line description of a file's first audio stream, e.g. `MP3 192 kbps 44.1 kHz stereo`
+///
+/// Falls back to the overall bitrate, which for VBR files is the average.
+pub fn describe_audio(info: &ProbeInfo) -> Option<String> {
+    let stream = info.audio()?;
+    let mut parts = vec![stream.codec.as_deref().unwrap_or("unknown codec").to_uppercase()];
+    if let Some(bitrate) = stream.bitrate_kbps.or(info.bitrate_kbps) {
+        parts.push(format!("{} kbps", bitrate));
+    }
+    if let Some(sample_rate) = stream.sample_rate {
+        parts.push(format!("{} kHz", sample_rate as f64 / 1000.0));
+    }
+    parts.push(match &stream.channel_layout {
+        Some(layout) => layout.clone(),
+        None => format!("{} channel(s)", stream.channels),
+    });
+    Some(parts.join(" "))
+}
+
+/// Machine-readable summary of a probed input's audio
+pub fn summary_json(input_path: &str, info: &ProbeInfo) -> serde_json::Value {
+    json!({
+        "input": input_path,
+        "duration_seconds": info.duration,
+        "bitrate_kbps": info.bitrate_kbps,
+        "audio_streams": info.audio_streams.iter().map(|stream| json!({
+            "codec": stream.codec,
+            "bitrate_kbps": stream.bitrate_kbps,
+            "sample_rate": stream.sample_rate,
+            "channels": stream.channels,
+            "channel_layout": stream.channel_layout,
+            "language": stream.language,
+        })).collect::<Vec<_>>(),
+        "chapter_count": info.chapters.len(),
+        "variable_frame_rate": info.variable_frame_rate,
+    })
+}
+
+/// Format seconds as `h:mm:ss` (or `m:ss` under an hour)
+pub fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (hours, minutes, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
@@ -0,0 +1,129 @@
+use crate::probe::format_timestamp;
+use indicatif::ProgressBar;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Set once spinners should no longer be drawn (`--oneline`)
+static SPINNERS_HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Stop drawing spinners and progress bars for the rest of the run
+pub fn hide_spinners() {
+    SPINNERS_HIDDEN.store(true, Ordering::Relaxed);
+}
+
+/// Whether spinners have been turned off
+pub fn spinners_hidden() -> bool {
+    SPINNERS_HIDDEN.load(Ordering::Relaxed)
+}
+
+/// Called with the tracker after every complete progress block
+pub type ProgressCallback = Arc<dyn Fn(&ProgressTracker) + Send + Sync>;
+
+/// Follows ffmpeg's `-progress` output and describes how far along a conversion is
+///
+/// ffmpeg writes blocks of `key=value` lines, each ending with a `progress=` line.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    /// Expected output length in seconds, when known
+    total_seconds: Option<f64>,
+    /// Output written so far, in seconds
+    out_seconds: f64,
+    /// Encode speed as a multiple of realtime
+    speed: Option<f64>,
+}
+
+impl ProgressTracker {
+    pub fn new(total_seconds: Option<f64>) -> Self {
+        Self {
+            total_seconds,
+            ..Self::default()
+        }
+    }
+
+    /// Feed one line of progress output, returning true when a block is complete
+    pub fn update(&mut self, line: &str) -> bool {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            return false;
+        };
+
+        match key {
+            // Despite the name, out_time_ms is in microseconds too
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<f64>() {
+                    self.out_seconds = (micros / 1_000_000.0).max(0.0);
+                }
+            }
+            // "3.2x", or "N/A" before the first frame
+            "speed" => {
+                self.speed = value
+                    .trim()
+                    .trim_end_matches('x')
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| *s > 0.0);
+            }
+            "progress" => return true,
+            _ => {}
+        }
+        false
+    }
+
+    /// Share of the expected output written so far, when the length is known
+    pub fn percent(&self) -> Option<f64> {
+        self.total_seconds
+            .map(|total| (self.out_seconds / total * 100.0).clamp(0.0, 100.0))
+    }
+
+    /// Encode speed as a multiple of realtime, once ffmpeg reports one
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Status text such as `42% • 3.2x • ETA 1:05`
+    pub fn message(&self) -> String {
+        let mut parts = Vec::new();
+
+        match self.percent() {
+            Some(percent) => parts.push(format!("{:.0}%", percent)),
+            None => parts.push(format!("{} done", format_timestamp(self.out_seconds))),
+        }
+
+        if let Some(speed) = self.speed {
+            parts.push(format!("{:.1}x", speed));
+            if let Some(total) = self.total_seconds {
+                let remaining = (total - self.out_seconds).max(0.0) / speed;
+                parts.push(format!("ETA {}", format_timestamp(remaining)));
+            }
+        }
+
+        parts.join(" • ")
+    }
+}
+
+/// Owns a progress bar and clears it when dropped, so a panic can't leave it stuck on screen
+pub struct SpinnerGuard {
+    bar: ProgressBar,
+}
+
+impl SpinnerGuard {
+    pub fn new(bar: ProgressBar) -> Self {
+        Self { bar }
+    }
+}
+
+impl Deref for SpinnerGuard {
+    type Target = ProgressBar;
+
+    fn deref(&self) -> &ProgressBar {
+        &self.bar
+    }
+}
+
+impl Drop for SpinnerGuard {
+    fn drop(&mut self) {
+        if !self.bar.is_finished() {
+            self.bar.finish_and_clear();
+        }
+    }
+}
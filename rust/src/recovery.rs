@@ -0,0 +1,128 @@
+use crate::ffmpeg;
+
+/// A known ffmpeg failure that extra flags usually get past
+pub struct Remedy {
+    /// What went wrong, as shown to the user
+    pub problem: &'static str,
+    /// Lowercase stderr fragments that identify the problem
+    patterns: &'static [&'static str],
+    /// Flags to add before `-i`
+    pub input_args: &'static [&'static str],
+    /// Flags to add before the output
+    pub output_args: &'static [&'static str],
+}
+
+impl Remedy {
+    /// All corrective flags, for logging
+    pub fn flags(&self) -> Vec<&'static str> {
+        self.input_args.iter().chain(self.output_args).copied().collect()
+    }
+}
+
+/// Recoverable failures, checked in order
+const REMEDIES: &[Remedy] = &[
+    // Common in .webm/.mkv screen recordings and some remuxed streams
+    Remedy {
+        problem: "broken timestamps",
+        patterns: &[
+            "timestamps are unset",
+            "non-monotonous dts",
+            "non monotonically increasing dts",
+            "pts has no value",
+            "invalid dts",
+        ],
+        input_args: &["-fflags", "+genpts"],
+        output_args: &[],
+    },
+    Remedy {
+        problem: "a full muxing queue",
+        patterns: &["too many packets buffered for output stream"],
+        input_args: &[],
+        output_args: &["-max_muxing_queue_size", "9999"],
+    },
+    Remedy {
+        problem: "stream parameters it couldn't detect",
+        patterns: &[
+            "could not find codec parameters",
+            "consider increasing the value for the 'analyzeduration'",
+        ],
+        input_args: &["-analyzeduration", "100M", "-probesize", "100M"],
+        output_args: &[],
+    },
+    Remedy {
+        problem: "corrupt packets",
+        patterns: &["packet corrupt", "corrupt input packet", "error while decoding stream"],
+        input_args: &["-err_detect", "ignore_err"],
+        output_args: &[],
+    },
+];
+
+/// Conservative input flags for `--safe-mode`: tolerate damage, regenerate
+/// timestamps, probe deeply and never touch the video stream
+pub const SAFE_MODE_INPUT_ARGS: &[&str] = &[
+    "-err_detect", "ignore_err",
+    "-fflags", "+genpts+discardcorrupt",
+    "-analyzeduration", "100M",
+    "-probesize", "100M",
+    "-vn", "-sn", "-dn",
+];
+
+/// Lowercase stderr fragments that mean an encrypted input couldn't be decrypted
+const ENCRYPTION_PATTERNS: &[&str] = &[
+    "unable to open key file",
+    "decryption key",
+    "encrypted",
+];
+
+/// Whether ffmpeg failed on an encrypted input: an unreachable HLS key or a missing `--key`
+///
+/// No flags fix this on their own, so it's reported rather than retried.
+pub fn is_encryption_failure(stderr: &[String]) -> bool {
+    let stderr = stderr.join("\n").to_lowercase();
+    ENCRYPTION_PATTERNS.iter().any(|pattern| stderr.contains(pattern))
+}
+
+/// Below this share of free memory, a killed ffmpeg was most likely the out-of-memory killer's doing
+const LOW_MEMORY_FRACTION: f64 = 0.1;
+
+/// Explain why ffmpeg may have been killed by `signal` and what to try instead
+///
+/// SIGKILL can't be caught, so ffmpeg never gets to say anything; the usual sender is the
+/// kernel's out-of-memory killer, which is near certain when memory is still short afterwards.
+pub fn explain_kill(signal: i32) -> Option<String> {
+    if ffmpeg::signal_name(signal) != "SIGKILL" {
+        return None;
+    }
+    let advice = "close other programs, limit ffmpeg's threads with --ffmpeg-threads 1, or pick a lower bitrate or sample rate";
+    Some(match available_memory_fraction() {
+        Some(free) if free < LOW_MEMORY_FRACTION => format!(
+            "🧠 FFmpeg was most likely stopped by the out-of-memory killer (only {:.0}% of memory is free); {}",
+            free * 100.0,
+            advice
+        ),
+        _ => format!(
+            "🧠 SIGKILL usually means the out-of-memory killer (or a service manager) stopped FFmpeg; if memory is tight, {}",
+            advice
+        ),
+    })
+}
+
+/// Share of memory the system could still hand out, from /proc/meminfo (`None` elsewhere)
+fn available_memory_fraction() -> Option<f64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| -> Option<f64> {
+        let line = meminfo.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].split_whitespace().next()?.parse().ok()
+    };
+    let (available, total) = (field("MemAvailable:")?, field("MemTotal:")?);
+    (total > 0.0).then(|| available / total)
+}
+
+/// Find a remedy for the failure described in ffmpeg's stderr, skipping ones already tried
+pub fn diagnose(stderr: &[String], already_applied: &[&str]) -> Option<&'static Remedy> {
+    let stderr = stderr.join("\n").to_lowercase();
+    REMEDIES.iter().find(|remedy| {
+        !already_applied.contains(&remedy.problem)
+            && remedy.patterns.iter().any(|pattern| stderr.contains(pattern))
+    })
+}
@@ -0,0 +1,96 @@
+use crate::tempdir;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A network-share input copied to local disk for `--stage-locally`, removed again on drop
+pub struct StagedInput {
+    pub path: PathBuf,
+}
+
+impl StagedInput {
+    /// The local copy's path as a string for ffmpeg
+    pub fn path_str(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for StagedInput {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Copy an input into the temp directory, keeping its file name so the extension still hints the format
+pub async fn stage_locally(input_path: &str) -> io::Result<StagedInput> {
+    let name = Path::new(input_path).file_name().unwrap_or_default().to_string_lossy();
+    let path = tempdir::path(&name)?;
+    let staged = StagedInput { path };
+    tokio::fs::copy(input_path, &staged.path).await?;
+    Ok(staged)
+}
+
+/// Name of the network filesystem a local path lives on (`SMB`, `NFS`, ...), if it looks like one
+///
+/// UNC paths are recognized everywhere; the filesystem type is checked on Linux and macOS.
+pub fn network_share(input_path: &str) -> Option<&'static str> {
+    if input_path.starts_with(r"\\") || input_path.starts_with("//") {
+        return Some("SMB");
+    }
+    filesystem_kind(Path::new(input_path))
+}
+
+/// Network filesystem type from statfs' magic number
+#[cfg(target_os = "linux")]
+fn filesystem_kind(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statfs is plain old data, so all-zero is a valid value to overwrite
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is NUL-terminated and stats is a valid, writable statfs
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+
+    // f_type's width varies by architecture; the magic numbers are all 32-bit
+    match stats.f_type as u32 {
+        0x6969 => Some("NFS"),
+        0x517B | 0xFF53_4D42 | 0xFE53_4D42 => Some("SMB"),
+        0x5346_414F => Some("AFS"),
+        0x0102_1997 => Some("9P"),
+        0x00C3_6400 => Some("Ceph"),
+        0x564C => Some("NetWare"),
+        _ => None,
+    }
+}
+
+/// Network filesystem type from statfs' type name
+#[cfg(target_os = "macos")]
+fn filesystem_kind(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statfs is plain old data, so all-zero is a valid value to overwrite
+    let mut stats: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is NUL-terminated and stats is a valid, writable statfs
+    if unsafe { libc::statfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+
+    // SAFETY: the kernel fills f_fstypename with a NUL-terminated name
+    let name = unsafe { std::ffi::CStr::from_ptr(stats.f_fstypename.as_ptr()) };
+    match name.to_bytes() {
+        b"nfs" => Some("NFS"),
+        b"smbfs" => Some("SMB"),
+        b"afpfs" => Some("AFP"),
+        b"webdav" => Some("WebDAV"),
+        _ => None,
+    }
+}
+
+/// Other platforms only get the path-based check
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn filesystem_kind(_path: &Path) -> Option<&'static str> {
+    None
+}
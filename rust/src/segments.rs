@@ -0,0 +1,91 @@
+use crate::error::ConverterError;
+use std::fs;
+
+/// One named span from a timestamps file
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub name: String,
+}
+
+/// Read a timestamps file with one `start end name` per line
+///
+/// Times are seconds or `[HH:]MM:SS[.fff]`; the name is the rest of the line.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn load(path: &str) -> Result<Vec<Segment>, ConverterError> {
+    let invalid = |reason: String| ConverterError::InvalidSegmentFile {
+        path: path.to_string(),
+        reason,
+    };
+
+    let contents = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let mut segments = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.splitn(3, char::is_whitespace);
+        let (Some(start), Some(end), Some(name)) = (fields.next(), fields.next(), fields.next()) else {
+            return Err(invalid(format!("line {}: expected `start end name`", number + 1)));
+        };
+        let time = |value: &str| {
+            parse_time(value).ok_or_else(|| invalid(format!("line {}: invalid time '{}'", number + 1, value)))
+        };
+        let (start, end) = (time(start)?, time(end.trim())?);
+
+        if end <= start {
+            return Err(invalid(format!("line {}: end must come after start", number + 1)));
+        }
+
+        segments.push(Segment {
+            start,
+            end,
+            name: name.trim().to_string(),
+        });
+    }
+
+    if segments.is_empty() {
+        return Err(invalid("no segments found".to_string()));
+    }
+    Ok(segments)
+}
+
+/// Parse `90`, `1:30`, `01:30.5` or `1:01:30` into seconds
+fn parse_time(value: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+
+    for (i, part) in parts.iter().enumerate() {
+        let number: f64 = part.parse().ok()?;
+        // Only the last field may have a fraction, and minutes/seconds stay below 60
+        if !number.is_finite() || number < 0.0 || (i > 0 && number >= 60.0) || (i + 1 < parts.len() && number.fract() != 0.0) {
+            return None;
+        }
+        seconds = seconds * 60.0 + number;
+    }
+    Some(seconds)
+}
+
+/// Pairs of segment names whose spans overlap
+pub fn overlaps(segments: &[Segment]) -> Vec<(&str, &str)> {
+    let mut sorted: Vec<&Segment> = segments.iter().collect();
+    sorted.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let mut found = Vec::new();
+    for (i, a) in sorted.iter().enumerate() {
+        for b in &sorted[i + 1..] {
+            if b.start >= a.end {
+                break;
+            }
+            found.push((a.name.as_str(), b.name.as_str()));
+        }
+    }
+    found
+}
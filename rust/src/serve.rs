@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+/// One line sent by a front-end to `--serve`
+///
+/// Either a conversion job (`{"input": "clip.mp4", "format": "opus"}`) or a
+/// control command (`{"command": "cancel"}`, `{"command": "shutdown"}`).
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Request {
+    Command { command: Command },
+    Job(Job),
+}
+
+/// Control commands understood by the server
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// Stop the job in progress, removing its partial output
+    Cancel,
+    /// Stop accepting jobs and remove the socket (a running job is cancelled first)
+    Shutdown,
+}
+
+/// A file to convert, with optional overrides of the server's settings
+#[derive(Debug, Deserialize)]
+pub struct Job {
+    pub input: String,
+    /// Output path; derived from the input like in the CLI when missing
+    pub output: Option<String>,
+    /// Output format name or extension (`mp3`, `m4a`, `opus`, `flac`, ...)
+    pub format: Option<String>,
+    /// Bitrate in kbps
+    pub bitrate: Option<u32>,
+}
+
+/// One line streamed back to the front-end
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// Sent on connect, once the server is ready for jobs
+    Ready { version: &'static str },
+    Started { input: String, output: String },
+    Progress { percent: Option<f64>, speed: Option<f64> },
+    Finished {
+        input: String,
+        output: String,
+        size_mb: Option<f64>,
+        /// Only with `--fingerprint`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fingerprint: Option<String>,
+    },
+    Failed { input: String, error: String },
+    Skipped { input: String },
+    /// The job was stopped by a `cancel` or `shutdown` command (or Ctrl+C on the server)
+    Cancelled { input: String },
+    /// A line that couldn't be understood; the connection stays open
+    Error { message: String },
+    Shutdown,
+}
+
+impl Event {
+    /// The event as a single JSON line
+    pub fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+/// Removes the socket file when the server stops
+#[cfg(unix)]
+pub struct SocketGuard {
+    path: String,
+}
+
+#[cfg(unix)]
+impl SocketGuard {
+    /// Bind a Unix socket, replacing a stale socket file left by an earlier run
+    pub fn bind(path: &str) -> std::io::Result<(tokio::net::UnixListener, Self)> {
+        use std::os::unix::fs::FileTypeExt;
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            let in_use = std::os::unix::net::UnixStream::connect(path).is_ok();
+            if metadata.file_type().is_socket() && !in_use {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)?;
+        Ok((listener, Self { path: path.to_string() }))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SocketGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -0,0 +1,80 @@
+use infer::{Infer, MatcherType, Type};
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Video container a file's first bytes identify, named like its usual extension
+///
+/// Only video containers are known; anything else (including audio-only MP4s) is `None`.
+pub fn video_container(path: &Path) -> io::Result<Option<&'static str>> {
+    Ok(detector().get_from_path(path)?.and_then(video_extension))
+}
+
+/// The extension of a detected type, if it's a video container
+fn video_extension(kind: Type) -> Option<&'static str> {
+    // The only custom types are the extra video containers below
+    matches!(kind.matcher_type(), MatcherType::Video | MatcherType::Custom).then(|| kind.extension())
+}
+
+/// `infer`'s detector, plus the containers it doesn't know that the converter scans for by default
+fn detector() -> &'static Infer {
+    static DETECTOR: OnceLock<Infer> = OnceLock::new();
+    DETECTOR.get_or_init(|| {
+        let mut detector = Infer::new();
+        detector.add("video/3gpp", "3gp", is_3gp);
+        detector.add("video/mp2t", "ts", is_mpeg_ts);
+        detector
+    })
+}
+
+/// An MPEG-4 file whose major brand is 3GPP (`3gp4`, `3gp5`, `3g2a`...)
+fn is_3gp(head: &[u8]) -> bool {
+    head.get(4..8) == Some(b"ftyp") && head.get(8..10) == Some(b"3g")
+}
+
+/// MPEG transport streams start two 188-byte packets with the same sync byte
+fn is_mpeg_ts(head: &[u8]) -> bool {
+    head.first() == Some(&0x47) && head.get(188) == Some(&0x47)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(head: &[u8]) -> Option<&'static str> {
+        detector().get(head).and_then(video_extension)
+    }
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut head = vec![0, 0, 0, 0x18];
+        head.extend_from_slice(b"ftyp");
+        head.extend_from_slice(brand);
+        head.resize(32, 0);
+        head
+    }
+
+    #[test]
+    fn recognizes_mpeg4_brands() {
+        assert_eq!(container(&ftyp(b"isom")), Some("mp4"));
+        assert_eq!(container(&ftyp(b"qt  ")), Some("mov"));
+        assert_eq!(container(&ftyp(b"3gp5")), Some("3gp"));
+    }
+
+    #[test]
+    fn rejects_audio_only_mpeg4() {
+        assert_eq!(container(&ftyp(b"M4A ")), None);
+    }
+
+    #[test]
+    fn recognizes_transport_streams() {
+        let mut head = vec![0; 376];
+        head[0] = 0x47;
+        head[188] = 0x47;
+        assert_eq!(container(&head), Some("ts"));
+    }
+
+    #[test]
+    fn rejects_text() {
+        assert_eq!(container(b"just some notes saved as lecture.mp4\n"), None);
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Symbols in use, set once from the config at startup
+static SYMBOLS: OnceLock<Symbols> = OnceLock::new();
+
+/// The statuses whose symbol can be changed in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Skip,
+    Convert,
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [Status::Success, Status::Failure, Status::Skip, Status::Convert];
+
+    /// Key in the `[symbols]` config section
+    pub fn key(self) -> &'static str {
+        match self {
+            Status::Success => "success",
+            Status::Failure => "failure",
+            Status::Skip => "skip",
+            Status::Convert => "convert",
+        }
+    }
+}
+
+/// The symbol printed in front of each status message
+#[derive(Debug, Clone)]
+pub struct Symbols {
+    success: String,
+    failure: String,
+    skip: String,
+    convert: String,
+}
+
+impl Default for Symbols {
+    fn default() -> Self {
+        Self {
+            success: "✅".to_string(),
+            failure: "❌".to_string(),
+            skip: "⏭️".to_string(),
+            convert: "🔄".to_string(),
+        }
+    }
+}
+
+impl Symbols {
+    /// Replace one status's symbol, checking that it can be displayed
+    ///
+    /// Success and failure symbols start the rows of `--benchmark`'s table, so they must
+    /// be a single grapheme (one character, or one emoji with its modifiers) to keep the
+    /// columns lined up. Skip and convert symbols may be short words like `[skip]`.
+    pub fn set(&mut self, status: Status, symbol: &str) -> Result<(), String> {
+        let symbol = symbol.trim();
+        if symbol.is_empty() || symbol.chars().any(|c| c.is_control() || c.is_whitespace()) {
+            return Err(format!("the {} symbol must be visible text without spaces, got \"{}\"", status.key(), symbol));
+        }
+        if matches!(status, Status::Success | Status::Failure) && !is_single_grapheme(symbol) {
+            return Err(format!(
+                "the {} symbol must be a single character or emoji so tables stay aligned, got \"{}\"",
+                status.key(),
+                symbol
+            ));
+        }
+
+        let slot = match status {
+            Status::Success => &mut self.success,
+            Status::Failure => &mut self.failure,
+            Status::Skip => &mut self.skip,
+            Status::Convert => &mut self.convert,
+        };
+        *slot = symbol.to_string();
+        Ok(())
+    }
+
+    fn get(&self, status: Status) -> &str {
+        match status {
+            Status::Success => &self.success,
+            Status::Failure => &self.failure,
+            Status::Skip => &self.skip,
+            Status::Convert => &self.convert,
+        }
+    }
+}
+
+/// Use these symbols for the rest of the run (only the first call has an effect)
+pub fn install(symbols: Symbols) {
+    let _ = SYMBOLS.set(symbols);
+}
+
+/// A status's symbol with the space that separates it from the message
+///
+/// Symbols ending in the emoji variation selector (like `⏭️`) are drawn wider than many
+/// terminals account for, so they get a second space.
+pub fn prefix(status: Status) -> String {
+    let symbol = SYMBOLS.get_or_init(Symbols::default).get(status);
+    let separator = if symbol.ends_with('\u{FE0F}') { "  " } else { " " };
+    format!("{}{}", symbol, separator)
+}
+
+/// A status message behind its symbol, e.g. `✅ Conversion completed successfully!`
+pub fn line(status: Status, message: impl Display) -> String {
+    format!("{}{}", prefix(status), message)
+}
+
+/// Whether `symbol` is drawn as one grapheme: a base character followed only by combining
+/// marks, variation selectors, skin tones or tags, or joined to more with zero-width joiners
+fn is_single_grapheme(symbol: &str) -> bool {
+    let mut chars = symbol.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    if is_extender(first) || first == '\u{200D}' {
+        return false;
+    }
+
+    // A flag is a pair of regional indicators
+    let mut regional_indicators = usize::from(is_regional_indicator(first));
+    let mut after_joiner = false;
+    for c in chars {
+        if after_joiner {
+            after_joiner = false;
+            continue;
+        }
+        match c {
+            '\u{200D}' => after_joiner = true,
+            c if is_extender(c) => {}
+            c if is_regional_indicator(c) && regional_indicators == 1 => regional_indicators = 2,
+            _ => return false,
+        }
+    }
+    !after_joiner
+}
+
+/// Characters that attach to the one before them instead of starting a new grapheme
+fn is_extender(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'       // combining diacritics
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{20D0}'..='\u{20FF}'     // combining marks for symbols, including the keycap
+        | '\u{FE00}'..='\u{FE0F}'     // variation selectors
+        | '\u{FE20}'..='\u{FE2F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'   // skin tones
+        | '\u{E0020}'..='\u{E007F}'   // tags (subdivision flags)
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
@@ -0,0 +1,71 @@
+use comfy_table::{Attribute, CellAlignment, LineStyle, Table, TableStyle};
+
+pub use comfy_table::Color;
+
+/// Longest cell shown in full; longer ones (usually file names) are shortened with "…"
+const MAX_CELL_CHARS: usize = 40;
+
+/// No borders, just a rule under the headings
+const STYLE: TableStyle = TableStyle::new().header_separator(LineStyle::none().fill('─').junction('─'));
+
+/// A table column: its heading and whether values line up on the right (numbers)
+pub struct Column {
+    pub heading: &'static str,
+    pub right_aligned: bool,
+}
+
+/// One table cell, optionally colored
+pub struct Cell {
+    pub text: String,
+    pub color: Option<Color>,
+}
+
+impl Cell {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: None }
+    }
+
+    pub fn colored(text: impl Into<String>, color: Color) -> Self {
+        Self { text: text.into(), color: Some(color) }
+    }
+}
+
+/// Print rows as an aligned table under a bold heading line
+///
+/// Colors are only drawn when stdout is a terminal.
+pub fn print(columns: &[Column], rows: &[Vec<Cell>]) {
+    let mut table = Table::new();
+    table.load_style(STYLE).style_text_only();
+    table.set_header(
+        columns
+            .iter()
+            .map(|column| comfy_table::Cell::new(column.heading).add_attribute(Attribute::Bold)),
+    );
+    for row in rows {
+        table.add_row(row.iter().map(|cell| {
+            let text = comfy_table::Cell::new(shorten(&cell.text));
+            match cell.color {
+                Some(color) => text.fg(color),
+                None => text,
+            }
+        }));
+    }
+    for (index, column) in columns.iter().enumerate() {
+        if let (true, Some(table_column)) = (column.right_aligned, table.column_mut(index)) {
+            table_column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+    println!("{}", table);
+}
+
+/// Cut overlong text down to `MAX_CELL_CHARS`, keeping the end (a file's extension)
+fn shorten(text: &str) -> String {
+    let count = text.chars().count();
+    if count <= MAX_CELL_CHARS {
+        return text.to_string();
+    }
+    let keep_end = 12;
+    let start: String = text.chars().take(MAX_CELL_CHARS - keep_end - 1).collect();
+    let end: String = text.chars().skip(count - keep_end).collect();
+    format!("{}…{}", start, end)
+}
@@ -0,0 +1,56 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+/// This run's temp directory, created when the first temp path is handed out
+static RUN_DIR: Mutex<Option<TempDir>> = Mutex::new(None);
+
+/// Numbers the handed-out paths so two files with the same name never collide
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// A fresh path for a temp file named like `file_name`, inside this run's temp directory
+///
+/// Everything in the directory is removed when the `Cleanup` guard in `main` is dropped
+/// (also while unwinding from a panic) or by `exit`, so callers don't have to clean up
+/// after themselves.
+pub fn path(file_name: &str) -> io::Result<PathBuf> {
+    let mut run_dir = RUN_DIR.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = match run_dir.as_ref() {
+        Some(dir) => dir.path().to_path_buf(),
+        None => {
+            let dir = tempfile::Builder::new().prefix("video-to-audio-").tempdir()?;
+            let path = dir.path().to_path_buf();
+            *run_dir = Some(dir);
+            path
+        }
+    };
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    Ok(dir.join(format!("{}-{}", id, file_name)))
+}
+
+/// Remove this run's temp directory and everything in it
+pub fn cleanup() {
+    let dir = RUN_DIR.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(dir) = dir {
+        let _ = dir.close();
+    }
+}
+
+/// Clean up temp files, then exit the process (use instead of `std::process::exit`)
+pub fn exit(code: i32) -> ! {
+    cleanup();
+    std::process::exit(code)
+}
+
+/// Removes this run's temp directory when dropped, however `main` is left
+///
+/// The directory lives in a static, which is never dropped, so `main` holds one of these.
+pub struct Cleanup;
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        cleanup();
+    }
+}
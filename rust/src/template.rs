@@ -0,0 +1,61 @@
+use crate::format::AudioFormat;
+use std::path::Path;
+
+/// Placeholders an output template may use
+pub const PLACEHOLDERS: [&str; 6] = ["name", "dir", "ext", "bitrate", "format", "index"];
+
+/// Fill `{placeholder}`s in a template; unknown ones are left as written
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = template.to_string();
+    for (key, value) in values {
+        rendered = rendered.replace(&format!("{{{}}}", key), value);
+    }
+    rendered
+}
+
+/// Check a template only uses known placeholders and yields `extension`, the one for `format`
+pub fn validate(template: &str, format: AudioFormat, extension: &str) -> Result<(), String> {
+    validate_placeholders(template)?;
+
+    let sample = render(
+        template,
+        &[
+            ("name", "name".to_string()),
+            ("dir", "dir".to_string()),
+            ("ext", extension.to_string()),
+            ("bitrate", "192".to_string()),
+            ("format", format.name().to_string()),
+            ("index", "001".to_string()),
+        ],
+    );
+    let rendered = Path::new(&sample).extension().and_then(|e| e.to_str()).unwrap_or_default();
+    if !rendered.eq_ignore_ascii_case(extension) {
+        return Err(format!(
+            "it must end in .{} for {} output (use .{{ext}} to follow the format)",
+            extension,
+            format
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a template only uses known placeholders and closes every `{`
+pub fn validate_placeholders(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            return Err("unclosed '{'".to_string());
+        };
+        let key = &rest[open + 1..open + close];
+        if !PLACEHOLDERS.contains(&key) {
+            return Err(format!(
+                "unknown placeholder {{{}}} (available: {})",
+                key,
+                PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+            ));
+        }
+        rest = &rest[open + close + 1..];
+    }
+    Ok(())
+}
@@ -1,5 +1,7 @@
+use crate::formats::Formats;
 use std::fmt;
 use std::path::Path;
+use std::time::SystemTime;
 
 /// Represents a video file with metadata
 #[derive(Debug, Clone)]
@@ -8,6 +10,10 @@ pub struct VideoFile {
     pub path: String,
     pub size_mb: f64,
     pub extension: String,
+    /// Last modification time, if the filesystem reports one
+    pub modified: Option<SystemTime>,
+    /// Scan root the file was discovered under
+    pub root: String,
 }
 
 impl VideoFile {
@@ -15,6 +21,7 @@ impl VideoFile {
     pub fn new(path: &Path) -> Result<Self, std::io::Error> {
         let metadata = std::fs::metadata(path)?;
         let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
+        let modified = metadata.modified().ok();
         
         let name = path
             .file_name()
@@ -27,27 +34,39 @@ impl VideoFile {
             .and_then(|ext| ext.to_str())
             .unwrap_or("")
             .to_lowercase();
+
+        let root = path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
         
         Ok(VideoFile {
             name,
             path: path.to_string_lossy().to_string(),
             size_mb,
             extension,
+            modified,
+            root,
         })
     }
     
-    /// Check if the file has a supported video extension
-    pub fn is_supported_video(&self) -> bool {
-        const SUPPORTED_EXTENSIONS: &[&str] = &[
-            "mp4", "avi", "mov", "mkv", "flv", "wmv", "webm", "m4v", "3gp"
-        ];
-        
-        SUPPORTED_EXTENSIONS.contains(&self.extension.as_str())
+    /// Whether the file has no content (0 bytes), e.g. left by an interrupted download
+    pub fn is_empty(&self) -> bool {
+        self.size_mb == 0.0
+    }
+
+    /// Check if the file has one of the video extensions listed in formats.toml
+    pub fn is_supported_video(&self, formats: &Formats) -> bool {
+        formats.is_video_extension(&self.extension)
     }
 }
 
 impl fmt::Display for VideoFile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ({:.1} MB)", self.name, self.size_mb)
+        if self.is_empty() {
+            write!(f, "{} (empty, 0 bytes)", self.name)
+        } else {
+            write!(f, "{} ({:.1} MB)", self.name, self.size_mb)
+        }
     }
 }